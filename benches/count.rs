@@ -0,0 +1,49 @@
+#![allow(clippy::needless_return)]
+//compares counting the lines of a large file with count_lines_of_file against reading the whole thing with read_to_string
+//run with `cargo bench`, and `cargo bench --no-default-features` to compare against streaming without memory maps
+use std::{
+    fs,
+    hint::black_box,
+    time::{Duration, Instant},
+};
+
+use line_counter as lib;
+
+//how big the generated file is
+const FILE_SIZE: usize = 256 * 1024 * 1024;
+//how many times each approach is timed, the fastest run is reported
+const RUNS: usize = 5;
+
+fn main() {
+    let path = std::env::temp_dir().join(format!("line_counter_bench_{}.txt", std::process::id()));
+    let line = "the quick brown fox jumps over the lazy dog, then does it again a few more times\n";
+    fs::write(&path, line.repeat(FILE_SIZE / line.len())).expect("Could not write benchmark file");
+    let path_name = path.to_str().unwrap();
+
+    let read_to_string = time(|| lib::count_lines(&fs::read_to_string(path_name).unwrap()));
+    let count_lines_of_file = time(|| lib::count_lines_of_file(path_name).unwrap());
+    assert_eq!(read_to_string.0, count_lines_of_file.0, "both approaches should count the same lines");
+
+    println!("{} Lines, {} MiB", read_to_string.0, FILE_SIZE / (1024 * 1024));
+    report("read_to_string", read_to_string.1);
+    report("count_lines_of_file", count_lines_of_file.1);
+
+    fs::remove_file(&path).expect("Could not remove benchmark file");
+}
+
+//runs count RUNS times, returns what it counted and the fastest time
+fn time(count: impl Fn() -> usize) -> (usize, Duration) {
+    let mut lines = 0;
+    let mut fastest = Duration::MAX;
+    for _ in 0..RUNS {
+        let start = Instant::now();
+        lines = black_box(count());
+        fastest = fastest.min(start.elapsed());
+    }
+    return (lines, fastest);
+}
+
+fn report(name: &str, duration: Duration) {
+    let throughput = FILE_SIZE as f64 / (1024.0 * 1024.0) / duration.as_secs_f64();
+    println!("{:<20}\t{:>8.2?}\t{:>8.0} MiB/s", name, duration, throughput);
+}
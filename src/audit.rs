@@ -0,0 +1,189 @@
+use std::fmt;
+
+/**
+ * a line ending or whitespace problem that can be made to fail an audit
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Check {
+    Lf, //any line ending in a bare '\n'
+    Crlf, //any line ending in "\r\n"
+    Cr, //any line ending in a lone '\r'
+    MixedLineEndings, //more than one kind of line ending in the same file
+    TrailingWhitespace,
+    MixedIndentation, //a space before a tab in the indentation of a line, or some lines indented with tabs and others with spaces
+    NoFinalNewline, //text after the last line ending
+}
+//every check, in the order they're listed in
+const CHECKS: [Check; 7] = [Check::Lf, Check::Crlf, Check::Cr, Check::MixedLineEndings, Check::TrailingWhitespace, Check::MixedIndentation, Check::NoFinalNewline];
+impl Check {
+    /**
+     * finds a check by the name it's displayed as, ignoring case
+     */
+    pub fn from_name(name: &str) -> Option<Check> {
+        return CHECKS.iter().find(|check| check.to_string().eq_ignore_ascii_case(name.trim())).copied();
+    }
+}
+impl fmt::Display for Check {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Check::Lf => write!(f, "LF"),
+            Check::Crlf => write!(f, "CRLF"),
+            Check::Cr => write!(f, "CR"),
+            Check::MixedLineEndings => write!(f, "MIXED-LINE-ENDINGS"),
+            Check::TrailingWhitespace => write!(f, "TRAILING-WHITESPACE"),
+            Check::MixedIndentation => write!(f, "MIXED-INDENTATION"),
+            Check::NoFinalNewline => write!(f, "NO-FINAL-NEWLINE"),
+        }
+    }
+}
+
+/**
+ * the line endings and whitespace of some text
+ * line numbers start at 1
+ */
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Hygiene {
+    pub lf: usize,
+    pub crlf: usize,
+    pub cr: usize,
+    pub trailing_whitespace: Vec<usize>, //lines that end in whitespace
+    pub mixed_indentation: Vec<usize>, //lines with a space before a tab in their indentation
+    pub tab_indented: usize, //lines indented with tabs
+    pub space_indented: usize, //lines indented with spaces
+    pub ends_with_newline: bool, //empty text counts as ending with one, since there's no line left unended
+}
+impl Hygiene {
+    /**
+     * checks if more than one kind of line ending was used
+     */
+    pub fn has_mixed_line_endings(&self) -> bool {
+        return [self.lf, self.crlf, self.cr].iter().filter(|n| **n > 0).count() > 1;
+    }
+
+    /**
+     * checks if the indentation is mixed, either within a line, or between lines
+     */
+    pub fn has_mixed_indentation(&self) -> bool {
+        return !self.mixed_indentation.is_empty() || (self.tab_indented > 0 && self.space_indented > 0);
+    }
+
+    /**
+     * returns which of checks this fails
+     */
+    pub fn violations(&self, checks: &[Check]) -> Vec<Check> {
+        return checks.iter().copied().filter(|check| match check {
+            Check::Lf => self.lf > 0,
+            Check::Crlf => self.crlf > 0,
+            Check::Cr => self.cr > 0,
+            Check::MixedLineEndings => self.has_mixed_line_endings(),
+            Check::TrailingWhitespace => !self.trailing_whitespace.is_empty(),
+            Check::MixedIndentation => self.has_mixed_indentation(),
+            Check::NoFinalNewline => !self.ends_with_newline,
+        }).collect();
+    }
+}
+impl fmt::Display for Hygiene {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} LF, {} CRLF, {} CR", self.lf, self.crlf, self.cr)?;
+        if let Some(first) = self.trailing_whitespace.first() {
+            write!(f, ", trailing whitespace on {} line(s) starting at line {}", self.trailing_whitespace.len(), first)?;
+        }
+        if let Some(first) = self.mixed_indentation.first() {
+            write!(f, ", space before tab in indentation on {} line(s) starting at line {}", self.mixed_indentation.len(), first)?;
+        }
+        if self.tab_indented > 0 && self.space_indented > 0 {
+            write!(f, ", {} line(s) indented with tabs and {} with spaces", self.tab_indented, self.space_indented)?;
+        }
+        if !self.ends_with_newline {
+            write!(f, ", no newline at end of file")?;
+        }
+        Ok(())
+    }
+}
+
+/**
+ * finds the line endings and whitespace problems in text
+ */
+pub fn audit(text: &str) -> Hygiene {
+    let mut hygiene = Hygiene { ends_with_newline: true, ..Hygiene::default() };
+    let mut rest = text;
+    let mut line_number = 0;
+    while !rest.is_empty() {
+        line_number += 1;
+        //split off the next line, and whatever ended it
+        let (line, ending_length) = match rest.find(['\n', '\r']) {
+            Some(end) if rest[end..].starts_with("\r\n") => {hygiene.crlf += 1; (&rest[..end], 2)},
+            Some(end) if rest[end..].starts_with('\r') => {hygiene.cr += 1; (&rest[..end], 1)},
+            Some(end) => {hygiene.lf += 1; (&rest[..end], 1)},
+            None => {hygiene.ends_with_newline = false; (rest, 0)},
+        };
+        rest = &rest[line.len() + ending_length..];
+
+        if line.ends_with(char::is_whitespace) {
+            hygiene.trailing_whitespace.push(line_number);
+        }
+        let indentation = &line[..line.len() - line.trim_start_matches([' ', '\t']).len()];
+        if indentation.contains(" \t") {
+            hygiene.mixed_indentation.push(line_number);
+        }
+        //lines that are only whitespace are already caught as trailing whitespace
+        if indentation.len() < line.len() {
+            match indentation.chars().next() {
+                Some('\t') => hygiene.tab_indented += 1,
+                Some(' ') => hygiene.space_indented += 1,
+                _ => {},
+            }
+        }
+    }
+    return hygiene;
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn count_line_endings() {
+        let hygiene = audit("unix\nwindows\r\nmac\rnone");
+        assert_eq!((hygiene.lf, hygiene.crlf, hygiene.cr), (1, 1, 1));
+        assert!(hygiene.has_mixed_line_endings());
+        assert!(!hygiene.ends_with_newline);
+
+        let hygiene = audit("a\r\nb\r\n");
+        assert_eq!((hygiene.lf, hygiene.crlf, hygiene.cr), (0, 2, 0));
+        assert!(!hygiene.has_mixed_line_endings());
+        assert!(hygiene.ends_with_newline);
+
+        assert!(audit("").ends_with_newline);
+    }
+    #[test]
+    fn find_whitespace_problems() {
+        let hygiene = audit("fn main() {  \n\tlet a = 1;\n \tlet b = 2;\n\t  // aligned\n    let c = 3;\n\t\n");
+        assert_eq!(hygiene.trailing_whitespace, vec![1, 6]);
+        assert_eq!(hygiene.mixed_indentation, vec![3]);
+        assert_eq!((hygiene.tab_indented, hygiene.space_indented), (2, 2));
+        assert!(hygiene.has_mixed_indentation());
+
+        let hygiene = audit("\tfn a() {\n\t\t  b();\n\t}\n");
+        assert!(hygiene.trailing_whitespace.is_empty());
+        assert!(!hygiene.has_mixed_indentation());
+    }
+    #[test]
+    fn violations() {
+        let hygiene = audit("a\r\nb \n");
+        let checks = [Check::Crlf, Check::Cr, Check::MixedLineEndings, Check::TrailingWhitespace, Check::NoFinalNewline];
+        assert_eq!(hygiene.violations(&checks), vec![Check::Crlf, Check::MixedLineEndings, Check::TrailingWhitespace]);
+        assert!(hygiene.violations(&[]).is_empty());
+    }
+    #[test]
+    fn check_names() {
+        assert_eq!(Check::from_name("mixed-line-endings"), Some(Check::MixedLineEndings));
+        assert_eq!(Check::from_name("CRLF"), Some(Check::Crlf));
+        assert_eq!(Check::from_name("tabs"), None);
+        for check in CHECKS {
+            assert_eq!(Check::from_name(&check.to_string()), Some(check));
+        }
+    }
+}
@@ -0,0 +1,73 @@
+use crate::encoding::{detect_bom, looks_like_utf16, DecodeOptions};
+
+//how many bytes at the start of a file are checked
+const BINARY_CHECK_SIZE: usize = 8192;
+//if more than this fraction of the bytes checked aren't valid UTF-8, it's binary
+const MAX_INVALID_RATIO: f64 = 0.3;
+
+/**
+ * guesses if some bytes from the start of a file are from a binary file, rather than text
+ * they are if they have a NUL byte, or if too many of them aren't valid UTF-8,
+ * unless they start with a byte order mark, or look like the UTF-16 options.fallback, since UTF-16 and UTF-32 text is full of NUL bytes
+ * if options.fallback is a single byte encoding, any byte is valid, so only NUL bytes count
+ */
+pub fn looks_binary(head: &[u8], options: &DecodeOptions) -> bool {
+    let head = &head[..head.len().min(BINARY_CHECK_SIZE)];
+    if head.is_empty() || detect_bom(head).is_some() {
+        return false;
+    }
+    if options.fallback.is_some_and(|fallback| looks_like_utf16(head, fallback)) {
+        return false;
+    }
+    if head.contains(&0) {
+        return true;
+    }
+    if options.fallback.is_some_and(|fallback| fallback.is_single_byte()) {
+        return false;
+    }
+
+    let invalid: usize = head.utf8_chunks().map(|chunk| chunk.invalid().len()).sum();
+    return invalid as f64 / head.len() as f64 > MAX_INVALID_RATIO;
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoding::Encoding;
+
+    #[test]
+    fn text_is_not_binary() {
+        assert!(!looks_binary(b"", &DecodeOptions::default()));
+        assert!(!looks_binary(b"fn main() {}\n", &DecodeOptions::default()));
+        assert!(!looks_binary("unicode is fine: \u{e9}\u{4e2d}\u{1f600}\n".as_bytes(), &DecodeOptions::default()));
+        //a little invalid UTF-8, like one Latin-1 character, is still text
+        assert!(!looks_binary(b"caf\xe9 au lait\n", &DecodeOptions::default()));
+        assert!(!looks_binary(b"\xff\xfeh\x00i\x00\n\x00", &DecodeOptions::default()));
+    }
+    #[test]
+    fn binary_is_binary() {
+        assert!(looks_binary(b"MZ\x90\x00\x03\x00\x00\x00", &DecodeOptions::default()));
+        assert!(looks_binary(b"\x89PNG\r\n\x1a\n\xff\xfe\xfd\xfc\xfb\xfa\xf9", &DecodeOptions::default()));
+    }
+    #[test]
+    fn cut_off_character_at_end_of_block() {
+        let mut head = "a".repeat(BINARY_CHECK_SIZE - 1).into_bytes();
+        head.push(0xe4); //first byte of a 3 byte character
+        assert!(!looks_binary(&head, &DecodeOptions::default()));
+    }
+    #[test]
+    fn fallback_encodings_are_not_binary() {
+        //UTF-16LE without a byte order mark is full of NULs
+        let utf16le: Vec<u8> = "fn main() {}\n".encode_utf16().flat_map(|unit| unit.to_le_bytes()).collect();
+        assert!(looks_binary(&utf16le, &DecodeOptions::default()));
+        assert!(!looks_binary(&utf16le, &DecodeOptions { fallback: Some(Encoding::Utf16Le), lossy: false }));
+        //Windows-1252 text with mostly non ASCII characters is too invalid to be UTF-8
+        let cp1252 = b"\x93\xe9t\xe9\x94 \xe0 \xe7\xe0";
+        assert!(looks_binary(cp1252, &DecodeOptions::default()));
+        assert!(!looks_binary(cp1252, &DecodeOptions { fallback: Some(Encoding::Windows1252), lossy: false }));
+        //but NULs still mean it's binary
+        assert!(looks_binary(b"MZ\x90\x00", &DecodeOptions { fallback: Some(Encoding::Windows1252), lossy: false }));
+    }
+}
@@ -0,0 +1,243 @@
+use std::ops::AddAssign;
+
+use crate::{
+    count::{split_lines, LineOptions},
+    language::Language,
+};
+
+/**
+ * number of code, comment, and blank lines in some text
+ */
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct LineStats {
+    pub code: usize,
+    pub comment: usize,
+    pub blank: usize,
+}
+impl LineStats {
+    /**
+     * total number of lines classified
+     */
+    pub fn total(&self) -> usize {
+        return self.code + self.comment + self.blank;
+    }
+}
+impl AddAssign for LineStats {
+    fn add_assign(&mut self, other: LineStats) {
+        self.code += other.code;
+        self.comment += other.comment;
+        self.blank += other.blank;
+    }
+}
+
+//what the scanner is in the middle of when it reaches the end of a line
+enum State {
+    Normal,
+    BlockComment { end: &'static str, depth: usize },
+    String { delimiter: &'static str },
+}
+
+//the length of the character literal at the start of text, like 'x', '\'', or '\u{1F600}'
+//None if text doesn't start with one, so a lifetime like 'a isn't mistaken for the start of one
+fn char_literal_length(text: &str) -> Option<usize> {
+    let body = text.strip_prefix('\'')?;
+    let c = body.chars().next()?;
+    if c == '\\' {
+        //an escape, which ends at the next quote after the escaped character
+        let escaped = body[1..].chars().next()?;
+        let tail = &body[1 + escaped.len_utf8()..];
+        let end = tail.char_indices().take_while(|(_, c)| !c.is_whitespace()).find(|(_, c)| *c == '\'')?.0;
+        return Some(text.len() - tail.len() + end + 1);
+    }
+    if c != '\'' && body[c.len_utf8()..].starts_with('\'') {
+        return Some(1 + c.len_utf8() + 1);
+    }
+    return None;
+}
+
+/**
+ * classify every line of file_contents as code, comment, or blank, using the comment and string syntax of language
+ * if language is None, every line that isn't blank is counted as code
+ * lines are split by options the same way they're counted, so the code, comment, and blank lines always add up to the line count
+ *
+ * a line with both code and a comment on it counts as code,
+ * and comment markers inside string literals are ignored
+ */
+pub fn classify_lines(file_contents: &str, language: Option<&Language>, options: &LineOptions) -> LineStats {
+    let mut stats = LineStats::default();
+    let mut state = State::Normal;
+
+    for line in split_lines(file_contents, options) {
+        //blank lines are blank, no matter what they're inside of
+        if line.trim().is_empty() {
+            stats.blank += 1;
+            continue;
+        }
+        let language = match language {
+            Some(l) => l,
+            None => {stats.code += 1; continue;},
+        };
+
+        let mut has_code = false;
+        let mut has_comment = matches!(state, State::BlockComment { .. });
+        let mut rest = line;
+        while let Some(c) = rest.chars().next() {
+            match state {
+                State::BlockComment { end, depth } => {
+                    has_comment = true;
+                    if let Some(after) = rest.strip_prefix(end) {
+                        rest = after;
+                        state = if depth > 1 { State::BlockComment { end, depth: depth - 1 } } else { State::Normal };
+                        continue;
+                    }
+                    if language.nested_block_comments {
+                        if let Some((start, _)) = language.block_comments.iter().find(|(start, e)| *e == end && rest.starts_with(start)) {
+                            rest = &rest[start.len()..];
+                            state = State::BlockComment { end, depth: depth + 1 };
+                            continue;
+                        }
+                    }
+                }
+                State::String { delimiter } => {
+                    has_code = true;
+                    if c == '\\' {
+                        //skip the escaped character too
+                        let mut chars = rest.chars();
+                        chars.next();
+                        chars.next();
+                        rest = chars.as_str();
+                        continue;
+                    }
+                    if let Some(after) = rest.strip_prefix(delimiter) {
+                        rest = after;
+                        state = State::Normal;
+                        continue;
+                    }
+                }
+                State::Normal => {
+                    if c.is_whitespace() {
+                        //whitespace doesn't make a line code or comment
+                    } else if let Some((start, end)) = language.block_comments.iter().find(|(start, _)| rest.starts_with(start)) {
+                        //block comments are checked first, since some start with a line comment marker, like Lua's --[[
+                        has_comment = true;
+                        rest = &rest[start.len()..];
+                        state = State::BlockComment { end, depth: 1 };
+                        continue;
+                    } else if language.line_comments.iter().any(|marker| rest.starts_with(marker)) {
+                        has_comment = true;
+                        break; //rest of the line is a comment
+                    } else if let Some(length) = language.char_literals.then(|| char_literal_length(rest)).flatten() {
+                        has_code = true;
+                        rest = &rest[length..];
+                        continue;
+                    } else if let Some(delimiter) = language.string_delimiters.iter().find(|d| rest.starts_with(*d)) {
+                        has_code = true;
+                        rest = &rest[delimiter.len()..];
+                        state = State::String { delimiter };
+                        continue;
+                    } else {
+                        has_code = true;
+                    }
+                }
+            }
+            rest = &rest[c.len_utf8()..];
+        }
+
+        if has_code {
+            stats.code += 1;
+        } else if has_comment {
+            stats.comment += 1;
+        } else {
+            stats.blank += 1;
+        }
+    }
+
+    return stats;
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::language;
+
+    #[test]
+    fn classify_rust() {
+        let text = "// a line comment\nfn main() {\n\n    /* a block\n       comment */\n    let x = 1; // trailing comment\n}\n";
+        let expected = LineStats { code: 3, comment: 3, blank: 1 };
+
+        assert_eq!(classify_lines(text, language::from_extension("rs"), &LineOptions::default()), expected);
+    }
+    #[test]
+    fn classify_comment_markers_in_strings() {
+        let text = "let url = \"http://example.com\";\nlet s = \"/* not a comment\";\nlet t = \"escaped \\\" // still a string\";\n";
+        let expected = LineStats { code: 3, comment: 0, blank: 0 };
+
+        assert_eq!(classify_lines(text, language::from_extension("rs"), &LineOptions::default()), expected);
+    }
+    #[test]
+    fn classify_nested_block_comments() {
+        let text = "/* outer\n/* inner */\nstill outer */\nlet x = 1;\n";
+        let expected = LineStats { code: 1, comment: 3, blank: 0 };
+
+        assert_eq!(classify_lines(text, language::from_extension("rs"), &LineOptions::default()), expected);
+        //C doesn't nest block comments, so the first */ ends the comment
+        let expected = LineStats { code: 2, comment: 2, blank: 0 };
+        assert_eq!(classify_lines(text, language::from_extension("c"), &LineOptions::default()), expected);
+    }
+    #[test]
+    fn classify_python_multiline_string() {
+        let text = "# comment\nx = \"\"\"\n# not a comment\n\"\"\"\n\n";
+        let expected = LineStats { code: 3, comment: 1, blank: 1 };
+
+        assert_eq!(classify_lines(text, language::from_extension("py"), &LineOptions::default()), expected);
+    }
+    #[test]
+    fn classify_lua_block_comments() {
+        let text = "--[[ a block\ncomment that\nspans lines\n]]\nlocal x = 1 -- trailing comment\nprint(x)\n";
+        let expected = LineStats { code: 2, comment: 4, blank: 0 };
+
+        assert_eq!(classify_lines(text, language::from_extension("lua"), &LineOptions::default()), expected);
+    }
+    #[test]
+    fn classify_rust_char_literals() {
+        let text = "let q = '\"';\n// a\n// b\nfn f() {}\n";
+        let expected = LineStats { code: 2, comment: 2, blank: 0 };
+        assert_eq!(classify_lines(text, language::from_extension("rs"), &LineOptions::default()), expected);
+
+        let text = "let q = '\\'';\nlet u = '\\u{22}';\nfn f<'a>(s: &'a str) -> &'a str { s }\n/* \"\n*/\n";
+        let expected = LineStats { code: 3, comment: 2, blank: 0 };
+        assert_eq!(classify_lines(text, language::from_extension("rs"), &LineOptions::default()), expected);
+    }
+    #[test]
+    fn classified_lines_add_up_to_line_count() {
+        use crate::count::{count_lines_in_text, LineTerminators};
+        let texts = [
+            "fn a() {}\n// c\nfn b() {}",
+            "fn a() {}\r// c\r\rfn b() {}\r",
+            "/* a\r\n b */\r\nlet x = 1;\r\n\r\n",
+            "// a\u{2028}let x = 1;\u{0C}\n\u{85}fn f() {}",
+            "",
+        ];
+        for text in texts {
+            for terminators in [LineTerminators::Lf, LineTerminators::Any, LineTerminators::Unicode] {
+                for count_final_line in [false, true] {
+                    let options = LineOptions { terminators, count_final_line };
+                    let stats = classify_lines(text, language::from_extension("rs"), &options);
+                    assert_eq!(stats.total(), count_lines_in_text(text, &options), "{:?} with {:?}", text, options);
+                }
+            }
+        }
+        //an old Mac file is three lines, not one
+        let options = LineOptions { terminators: LineTerminators::Any, count_final_line: false };
+        assert_eq!(classify_lines("fn a() {}\r// c\r\r", language::from_extension("rs"), &options), LineStats { code: 1, comment: 1, blank: 1 });
+    }
+    #[test]
+    fn classify_unknown_language() {
+        let text = "# this is code\n\nsome text\n";
+        let expected = LineStats { code: 2, comment: 0, blank: 1 };
+
+        assert_eq!(classify_lines(text, None, &LineOptions::default()), expected);
+    }
+}
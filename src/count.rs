@@ -0,0 +1,366 @@
+use std::{
+    fs::File,
+    io::{self, Read},
+};
+#[cfg(feature = "mmap")]
+use std::io::Seek;
+
+//how many bytes are read at a time when streaming
+const BUFFER_SIZE: usize = 64 * 1024;
+//files smaller than this are streamed, setting up a mapping costs more than it saves on them
+#[cfg(feature = "mmap")]
+const MMAP_THRESHOLD: u64 = 1024 * 1024;
+
+//a byte with every bit but the highest set, repeated across a word
+const LOW_SEVEN_BITS: u64 = 0x7F7F_7F7F_7F7F_7F7F;
+//a byte with only the lowest bit set, repeated across a word
+const ONES: u64 = 0x0101_0101_0101_0101;
+
+/**
+ * which characters end a line
+ */
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum LineTerminators {
+    #[default]
+    Lf, //only '\n', a "\r\n" counts once since it ends in one
+    Any, //'\n', "\r\n", and a lone '\r', like old Mac files use
+    Unicode, //everything Any does, and vertical tab, form feed, next line (U+0085), line separator (U+2028), and paragraph separator (U+2029)
+}
+
+/**
+ * how lines are counted
+ */
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct LineOptions {
+    pub terminators: LineTerminators,
+    pub count_final_line: bool, //count text after the last line terminator as a line too, like editors do
+}
+
+/**
+ * counts the lines in text, which can be split across as many calls to count as needed
+ * only works on text where '\n' and '\r' are single bytes, like UTF-8 and Latin-1,
+ * and doesn't recognize the extra terminators of LineTerminators::Unicode, count_lines_in_text does
+ */
+#[derive(Debug)]
+pub struct LineCounter {
+    options: LineOptions,
+    lines: usize,
+    bytes: usize,
+    last_byte: Option<u8>, //the last byte counted so far, for a "\r\n" split between two calls
+}
+impl LineCounter {
+    pub fn new(options: LineOptions) -> LineCounter {
+        return LineCounter { options, lines: 0, bytes: 0, last_byte: None };
+    }
+
+    /**
+     * counts the lines ended in bytes
+     */
+    pub fn count(&mut self, bytes: &[u8]) {
+        let first_byte = match bytes.first() {
+            Some(b) => *b,
+            None => return,
+        };
+        self.bytes += bytes.len();
+        self.lines += count_byte(bytes, b'\n');
+        if self.options.terminators != LineTerminators::Lf {
+            //every '\r' ends a line, unless it's followed by a '\n', which ends the same line
+            let carriage_returns = count_byte(bytes, b'\r');
+            if carriage_returns > 0 || self.last_byte == Some(b'\r') {
+                let pairs = bytes.windows(2).filter(|pair| pair == b"\r\n").count();
+                let split_pair = (self.last_byte == Some(b'\r') && first_byte == b'\n') as usize;
+                self.lines = self.lines + carriage_returns - pairs - split_pair;
+            }
+        }
+        self.last_byte = bytes.last().copied();
+    }
+
+    /**
+     * counts everything read from reader, one buffer at a time,
+     * so memory use stays the same no matter how much there is to read
+     */
+    pub fn count_reader<R: Read>(&mut self, mut reader: R) -> io::Result<()> {
+        let mut buffer = vec![0; BUFFER_SIZE];
+        loop {
+            match reader.read(&mut buffer) {
+                Ok(0) => return Ok(()),
+                Ok(n) => self.count(&buffer[..n]),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /**
+     * counts the rest of file, starting from wherever it's been read up to
+     * large regular files are counted straight from a memory map without copying them,
+     * anything else, like small files, pipes, or files on filesystems that can't be mapped, is streamed instead
+     */
+    pub fn count_file(&mut self, file: &mut File) -> io::Result<()> {
+        #[cfg(feature = "mmap")]
+        if self.count_mapped(file) {
+            return Ok(());
+        }
+        return self.count_reader(file);
+    }
+
+    //counts the rest of file from a memory map, returns false if it's too small or can't be mapped
+    #[cfg(feature = "mmap")]
+    fn count_mapped(&mut self, file: &mut File) -> bool {
+        let metadata = match file.metadata() {
+            Ok(m) => m,
+            Err(_) => return false,
+        };
+        if !metadata.is_file() || metadata.len() < MMAP_THRESHOLD {
+            return false;
+        }
+        let position = match file.stream_position() {
+            Ok(p) => p as usize,
+            Err(_) => return false,
+        };
+        //SAFETY: the mapping is only read, and dropped before returning
+        //if another process truncates the file while it's being counted, this can crash, the same way it could for any other tool that maps files
+        let map = match unsafe { memmap2::Mmap::map(&*file) } {
+            Ok(m) => m,
+            Err(_) => return false,
+        };
+        self.count(map.get(position..).unwrap_or_default());
+        return true;
+    }
+
+    /**
+     * returns how many bytes were counted
+     */
+    pub fn bytes(&self) -> usize {
+        return self.bytes;
+    }
+
+    /**
+     * returns how many lines were counted
+     */
+    pub fn finish(&self) -> usize {
+        let unterminated = match self.last_byte {
+            None => false,
+            Some(b'\n') => false,
+            Some(b'\r') => self.options.terminators == LineTerminators::Lf,
+            Some(_) => true,
+        };
+        return self.lines + (self.options.count_final_line && unterminated) as usize;
+    }
+}
+
+/**
+ * counts the lines in text, recognizing every terminator options.terminators asks for
+ */
+pub fn count_lines_in_text(text: &str, options: &LineOptions) -> usize {
+    if options.terminators != LineTerminators::Unicode {
+        let mut counter = LineCounter::new(*options);
+        counter.count(text.as_bytes());
+        return counter.finish();
+    }
+
+    let mut lines = 0;
+    let mut previous = None;
+    for c in text.chars() {
+        match c {
+            '\n' if previous == Some('\r') => {}, //the second half of a "\r\n", which was counted at the '\r'
+            '\n' | '\r' | '\u{0B}' | '\u{0C}' | '\u{85}' | '\u{2028}' | '\u{2029}' => lines += 1,
+            _ => {},
+        }
+        previous = Some(c);
+    }
+    let unterminated = previous.is_some_and(|c| !['\n', '\r', '\u{0B}', '\u{0C}', '\u{85}', '\u{2028}', '\u{2029}'].contains(&c));
+    return lines + (options.count_final_line && unterminated) as usize;
+}
+
+/**
+ * the lines of text, split the same way count_lines_in_text counts them, so there are always as many
+ * text after the last line terminator is only a line if options.count_final_line is set
+ * the terminators aren't part of the lines, except a '\r' before a '\n' with LineTerminators::Lf
+ */
+pub fn split_lines<'a>(text: &'a str, options: &LineOptions) -> SplitLines<'a> {
+    return SplitLines { rest: text, options: *options };
+}
+
+/**
+ * the lines of some text, see split_lines
+ */
+pub struct SplitLines<'a> {
+    rest: &'a str,
+    options: LineOptions,
+}
+impl<'a> Iterator for SplitLines<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        let rest = self.rest;
+        if rest.is_empty() {
+            return None;
+        }
+        let terminators = self.options.terminators;
+        for (i, c) in rest.char_indices() {
+            let terminator_length = match c {
+                '\n' => 1,
+                '\r' if terminators != LineTerminators::Lf => if rest[i + 1..].starts_with('\n') { 2 } else { 1 },
+                '\u{0B}' | '\u{0C}' | '\u{85}' | '\u{2028}' | '\u{2029}' if terminators == LineTerminators::Unicode => c.len_utf8(),
+                _ => continue,
+            };
+            self.rest = &rest[i + terminator_length..];
+            return Some(&rest[..i]);
+        }
+        self.rest = "";
+        return self.options.count_final_line.then_some(rest);
+    }
+}
+
+/**
+ * counts the '\n' bytes in bytes
+ */
+pub fn count_newlines(bytes: &[u8]) -> usize {
+    return count_byte(bytes, b'\n');
+}
+
+//counts how many times an ASCII byte appears in bytes
+//works on 8 bytes at a time, instead of checking each byte one by one
+fn count_byte(bytes: &[u8], byte: u8) -> usize {
+    let pattern = ONES * byte as u64;
+    let mut chunks = bytes.chunks_exact(8);
+    let mut count = 0;
+    for chunk in &mut chunks {
+        //bytes that matched are now 0
+        let word = u64::from_ne_bytes(chunk.try_into().unwrap()) ^ pattern;
+        //the high bit of each byte ends up set only if the byte was 0, adding 0x7F to the low bits can't carry into the next byte
+        let zero_bytes = !(((word & LOW_SEVEN_BITS) + LOW_SEVEN_BITS) | word | LOW_SEVEN_BITS);
+        count += zero_bytes.count_ones() as usize;
+    }
+    count += chunks.remainder().iter().filter(|b| **b == byte).count();
+    return count;
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn count_with(text: &str, terminators: LineTerminators, count_final_line: bool) -> usize {
+        return count_lines_in_text(text, &LineOptions { terminators, count_final_line });
+    }
+    //counts one byte at a time, so every "\r\n" in bytes is split between calls
+    fn count_byte_by_byte(bytes: &[u8], options: LineOptions) -> usize {
+        let mut counter = LineCounter::new(options);
+        for byte in bytes {
+            counter.count(std::slice::from_ref(byte));
+        }
+        return counter.finish();
+    }
+
+    #[test]
+    fn newlines_in_every_position() {
+        //make sure every byte of a word, and the leftovers after the last word, are checked
+        for length in 0..40 {
+            for position in 0..length {
+                let mut bytes = vec![b'a'; length];
+                bytes[position] = b'\n';
+                assert_eq!(count_newlines(&bytes), 1, "length {}, position {}", length, position);
+            }
+        }
+        assert_eq!(count_newlines(&[b'\n'; 21]), 21);
+    }
+    #[test]
+    fn bytes_that_look_like_newlines() {
+        //bytes that only differ from '\n' by one bit, and bytes with the high bit set
+        let bytes = [0x0B, 0x08, 0x8A, 0x1A, 0x00, 0xFF, 0x0A, 0x4A, 0x09, 0x0E, 0x02, 0xFA];
+        assert_eq!(count_newlines(&bytes), 1);
+    }
+    #[test]
+    fn lf_terminators() {
+        assert_eq!(count_with("a\nb\r\nc\rd", LineTerminators::Lf, false), 2);
+        assert_eq!(count_with("a\nb\r\nc\rd", LineTerminators::Lf, true), 3);
+        assert_eq!(count_with("a\nb\n", LineTerminators::Lf, true), 2);
+        //a trailing '\r' doesn't end a line when only '\n' does
+        assert_eq!(count_with("a\r", LineTerminators::Lf, true), 1);
+    }
+    #[test]
+    fn any_terminators() {
+        assert_eq!(count_with("a\nb\r\nc\rd", LineTerminators::Any, false), 3);
+        assert_eq!(count_with("a\nb\r\nc\rd", LineTerminators::Any, true), 4);
+        assert_eq!(count_with("old\rmac\rfile\r", LineTerminators::Any, true), 3);
+        assert_eq!(count_with("\r\r\n\n\r", LineTerminators::Any, false), 4);
+        //unicode separators are just text
+        assert_eq!(count_with("a\u{2028}b", LineTerminators::Any, false), 0);
+    }
+    #[test]
+    fn unicode_terminators() {
+        assert_eq!(count_with("a\nb\r\nc\rd", LineTerminators::Unicode, false), 3);
+        assert_eq!(count_with("a\u{85}b\u{2028}c\u{2029}d\u{0B}e\u{0C}", LineTerminators::Unicode, false), 5);
+        assert_eq!(count_with("a\u{2028}b", LineTerminators::Unicode, true), 2);
+        assert_eq!(count_with("a\u{2029}", LineTerminators::Unicode, true), 1);
+    }
+    #[test]
+    fn final_line_of_empty_text() {
+        for terminators in [LineTerminators::Lf, LineTerminators::Any, LineTerminators::Unicode] {
+            assert_eq!(count_with("", terminators, true), 0);
+        }
+    }
+    #[test]
+    fn crlf_split_between_buffers() {
+        let bytes = b"a\r\nb\r\r\nc\rd\r";
+        for count_final_line in [false, true] {
+            let options = LineOptions { terminators: LineTerminators::Any, count_final_line };
+            let mut whole = LineCounter::new(options);
+            whole.count(bytes);
+            assert_eq!(count_byte_by_byte(bytes, options), whole.finish());
+        }
+        assert_eq!(count_byte_by_byte(bytes, LineOptions { terminators: LineTerminators::Any, count_final_line: false }), 5);
+    }
+    #[test]
+    fn stream_across_buffers() {
+        //more than one buffer, with a newline right at the boundary
+        let mut bytes = vec![b'x'; BUFFER_SIZE * 3 + 17];
+        bytes[BUFFER_SIZE - 1] = b'\n';
+        bytes[BUFFER_SIZE] = b'\n';
+        bytes[BUFFER_SIZE * 3 + 16] = b'\n';
+        let mut counter = LineCounter::new(LineOptions::default());
+        counter.count_reader(&bytes[..]).unwrap();
+        assert_eq!(counter.finish(), 3);
+
+        //a "\r\n" right at the boundary
+        bytes[BUFFER_SIZE - 1] = b'\r';
+        let mut counter = LineCounter::new(LineOptions { terminators: LineTerminators::Any, count_final_line: false });
+        counter.count_reader(&bytes[..]).unwrap();
+        assert_eq!(counter.finish(), 2);
+    }
+    #[test]
+    fn count_rest_of_file() {
+        //one file small enough to be streamed, and one big enough to be mapped
+        let path = std::env::temp_dir().join(format!("line_counter_count_test_{}", std::process::id()));
+        for length in [100, 3 * 1024 * 1024] {
+            let mut bytes = vec![b'x'; length];
+            bytes[0] = b'\n';
+            bytes[length / 2] = b'\n';
+            bytes[length - 1] = b'\n';
+            std::fs::write(&path, &bytes).unwrap();
+
+            let mut file = File::open(&path).unwrap();
+            let mut counter = LineCounter::new(LineOptions::default());
+            counter.count_file(&mut file).unwrap();
+            assert_eq!(counter.finish(), 3, "length {}", length);
+            //skipping the first byte skips the first newline
+            let mut file = File::open(&path).unwrap();
+            file.read_exact(&mut [0]).unwrap();
+            let mut counter = LineCounter::new(LineOptions::default());
+            counter.count_file(&mut file).unwrap();
+            assert_eq!(counter.finish(), 2, "length {}", length);
+        }
+        std::fs::remove_file(&path).unwrap();
+    }
+    #[test]
+    fn stream_lots_of_lines() {
+        let reader = io::repeat(b'\n').take(10 * 1024 * 1024 + 3);
+        let mut counter = LineCounter::new(LineOptions::default());
+        counter.count_reader(reader).unwrap();
+        assert_eq!(counter.finish(), 10 * 1024 * 1024 + 3);
+        assert_eq!(counter.bytes(), 10 * 1024 * 1024 + 3);
+    }
+}
@@ -0,0 +1,344 @@
+use crate::{
+    report::{show_summary, Report, Totals},
+    Config, FileCount,
+};
+
+/**
+ * what separates the fields of a row
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Separator {
+    Comma, //CSV, as in RFC 4180
+    Tab, //TSV, as in the IANA text/tab-separated-values type
+}
+
+/**
+ * one column of a table of files, only the ones that config asks for are written
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Column {
+    RowType, //what a row of a CSV or TSV table is: a file, a file that couldn't be counted, or the totals
+    Path,
+    Language,
+    Encoding,
+    Size,
+    Lines,
+    Code,
+    Comments,
+    Blank,
+    Words,
+    Chars,
+    Graphemes,
+    Bytes,
+    MaxLineBytes,
+    MaxLineColumns,
+    LongLines,
+    Error,
+}
+impl Column {
+    /**
+     * the columns a table of files has, based on what config counts
+     */
+    pub fn for_config(config: &Config) -> Vec<Column> {
+        let measures = &config.measures;
+        let mut columns = vec![Column::Path, Column::Language, Column::Encoding, Column::Size, Column::Lines];
+        if config.classify_lines {
+            columns.extend([Column::Code, Column::Comments, Column::Blank]);
+        }
+        //the wc style counts, in the same order wc uses
+        if measures.words {
+            columns.push(Column::Words);
+        }
+        if measures.chars {
+            columns.push(Column::Chars);
+        }
+        if measures.graphemes {
+            columns.push(Column::Graphemes);
+        }
+        if measures.bytes {
+            columns.push(Column::Bytes);
+        }
+        if measures.max_line_length {
+            columns.extend([Column::MaxLineBytes, Column::MaxLineColumns]);
+        }
+        if config.long_line_limit.is_some() {
+            columns.push(Column::LongLines);
+        }
+        columns.push(Column::Error);
+        return columns;
+    }
+
+    /**
+     * the name of the column, for the header row
+     */
+    pub fn name(&self) -> &'static str {
+        match self {
+            Column::RowType => "type",
+            Column::Path => "path",
+            Column::Language => "language",
+            Column::Encoding => "encoding",
+            Column::Size => "size",
+            Column::Lines => "lines",
+            Column::Code => "code",
+            Column::Comments => "comments",
+            Column::Blank => "blank",
+            Column::Words => "words",
+            Column::Chars => "chars",
+            Column::Graphemes => "graphemes",
+            Column::Bytes => "bytes",
+            Column::MaxLineBytes => "max_line_bytes",
+            Column::MaxLineColumns => "max_line_columns",
+            Column::LongLines => "long_lines",
+            Column::Error => "error",
+        }
+    }
+
+    /**
+     * the name of the column, for people to read, like in the header of a markdown or HTML table
+     */
+    pub fn title(&self) -> &'static str {
+        match self {
+            Column::RowType => "Type",
+            Column::Path => "File",
+            Column::Language => "Language",
+            Column::Encoding => "Encoding",
+            Column::Size => "Size",
+            Column::Lines => "Lines",
+            Column::Code => "Code",
+            Column::Comments => "Comments",
+            Column::Blank => "Blanks",
+            Column::Words => "Words",
+            Column::Chars => "Chars",
+            Column::Graphemes => "Graphemes",
+            Column::Bytes => "Bytes",
+            Column::MaxLineBytes => "Max Line Bytes",
+            Column::MaxLineColumns => "Max Line Columns",
+            Column::LongLines => "Long Lines",
+            Column::Error => "Error",
+        }
+    }
+
+    /**
+     * checks if the values of the column are numbers, which tables right align
+     */
+    pub fn is_number(&self) -> bool {
+        return !matches!(self, Column::RowType | Column::Path | Column::Language | Column::Encoding | Column::Error);
+    }
+
+    /**
+     * the value of the column for a file that was counted, empty if it wasn't measured
+     */
+    pub fn value(&self, path: &str, count: &FileCount) -> String {
+        let measurements = &count.measurements;
+        let number = |n: Option<usize>| n.map_or(String::new(), |n| n.to_string());
+        match self {
+            Column::RowType => return "file".to_string(),
+            Column::Path => return path.to_string(),
+            Column::Language => return count.language.map_or(String::new(), |l| l.name.to_string()),
+            Column::Encoding => return count.encoding.to_string(),
+            Column::Size => return count.size.to_string(),
+            Column::Lines => return count.lines.to_string(),
+            Column::Code => return number(count.line_stats.map(|s| s.code)),
+            Column::Comments => return number(count.line_stats.map(|s| s.comment)),
+            Column::Blank => return number(count.line_stats.map(|s| s.blank)),
+            Column::Words => return number(measurements.words),
+            Column::Chars => return number(measurements.chars),
+            Column::Graphemes => return number(measurements.graphemes),
+            Column::Bytes => return number(measurements.bytes),
+            Column::MaxLineBytes => return number(measurements.max_line_bytes),
+            Column::MaxLineColumns => return number(measurements.max_line_width),
+            Column::LongLines => return number(count.line_lengths.as_ref().map(|l| l.long_lines.len())),
+            Column::Error => return String::new(),
+        }
+    }
+
+    /**
+     * the value of the column for the totals of a run, empty if it can't be added up
+     */
+    pub fn total(&self, totals: &Totals) -> String {
+        let measurements = &totals.measurements;
+        let number = |n: Option<usize>| n.map_or(String::new(), |n| n.to_string());
+        let stat = |get: fn(&crate::LineStats) -> usize| totals.line_stats.as_ref().map_or(String::new(), |s| get(s).to_string());
+        match self {
+            Column::RowType => return "total".to_string(),
+            Column::Size => return totals.size.to_string(),
+            Column::Lines => return totals.lines.to_string(),
+            Column::Code => return stat(|s| s.code),
+            Column::Comments => return stat(|s| s.comment),
+            Column::Blank => return stat(|s| s.blank),
+            Column::Words => return number(measurements.words),
+            Column::Chars => return number(measurements.chars),
+            Column::Graphemes => return number(measurements.graphemes),
+            Column::Bytes => return number(measurements.bytes),
+            Column::MaxLineBytes => return number(measurements.max_line_bytes),
+            Column::MaxLineColumns => return number(measurements.max_line_width),
+            Column::LongLines => return totals.long_lines.to_string(),
+            _ => return String::new(),
+        }
+    }
+}
+
+/**
+ * writes a field so it can't be mistaken for more than one
+ * CSV fields with a comma, quote, or line break are quoted, with quotes doubled
+ * TSV fields can't be quoted, so tabs, line breaks, and backslashes are escaped with a backslash instead
+ */
+pub fn escape_field(field: &str, separator: Separator) -> String {
+    match separator {
+        Separator::Comma => {
+            if field.contains([',', '"', '\r', '\n']) {
+                return format!("\"{}\"", field.replace('"', "\"\""));
+            }
+            return field.to_string();
+        },
+        Separator::Tab => {
+            return field.replace('\\', "\\\\").replace('\t', "\\t").replace('\n', "\\n").replace('\r', "\\r");
+        },
+    }
+}
+
+/**
+ * joins fields into one row, including the line ending, which is CRLF for CSV like RFC 4180 says
+ */
+pub fn row<S: AsRef<str>>(fields: &[S], separator: Separator) -> String {
+    let (delimiter, line_ending) = match separator {
+        Separator::Comma => (",", "\r\n"),
+        Separator::Tab => ("\t", "\n"),
+    };
+    let fields: Vec<String> = fields.iter().map(|field| escape_field(field.as_ref(), separator)).collect();
+    return fields.join(delimiter) + line_ending;
+}
+
+//the columns of the table written with config.summary_only
+const SUMMARY_COLUMNS: [&str; 6] = ["files", "lines", "size", "skipped", "failed", "elapsed_seconds"];
+
+/**
+ * --format=CSV and --format=TSV, a header row, then a row for every file that was counted, skipped as binary, or couldn't be,
+ * and a totals row with the sum of every column that can be added up
+ * the first column says which kind of row each one is, file, binary, error, or total, so a file can't be mistaken for the totals
+ * with config.summary_only, the table is just the totals, with a column for each
+ */
+pub struct DelimitedReport<'a> {
+    config: &'a Config,
+    columns: Vec<Column>,
+    separator: Separator,
+}
+impl DelimitedReport<'_> {
+    pub fn new(config: &Config, separator: Separator) -> DelimitedReport<'_> {
+        let mut columns = Column::for_config(config);
+        columns.insert(0, Column::RowType);
+        if config.summary_only {
+            print!("{}", row(&SUMMARY_COLUMNS, separator));
+        } else {
+            let names: Vec<&str> = columns.iter().map(|column| column.name()).collect();
+            print!("{}", row(&names, separator));
+        }
+        return DelimitedReport { config, columns, separator };
+    }
+
+    //a row for a file that wasn't counted, so only the kind of row, the path, and the error if there is one are filled in
+    fn uncounted_row(&self, row_type: &str, path: &str, message: &str) -> String {
+        let fields: Vec<&str> = self.columns.iter().map(|column| match column {
+            Column::RowType => row_type,
+            Column::Path => path,
+            Column::Error => message,
+            _ => "",
+        }).collect();
+        return row(&fields, self.separator);
+    }
+}
+impl Report for DelimitedReport<'_> {
+    fn file(&mut self, path: &str, count: &FileCount) {
+        let fields: Vec<String> = self.columns.iter().map(|column| column.value(path, count)).collect();
+        print!("{}", row(&fields, self.separator));
+    }
+
+    fn failed(&mut self, path: &str, message: &str) {
+        print!("{}", self.uncounted_row("error", path, message));
+    }
+
+    fn binary(&mut self, path: &str) {
+        print!("{}", self.uncounted_row("binary", path, ""));
+    }
+
+    fn finish(&mut self, totals: &Totals) {
+        if self.config.summary_only {
+            let fields = [
+                totals.files.to_string(),
+                totals.lines.to_string(),
+                totals.size.to_string(),
+                totals.skipped_files().to_string(),
+                totals.failed_files.to_string(),
+                format!("{:.3}", totals.elapsed.as_secs_f64()),
+            ];
+            print!("{}", row(&fields, self.separator));
+            return;
+        }
+        if !show_summary(self.config) {
+            return;
+        }
+        let fields: Vec<String> = self.columns.iter().map(|column| column.total(totals)).collect();
+        print!("{}", row(&fields, self.separator));
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::measure::Measurements;
+
+    #[test]
+    fn escape_csv() {
+        assert_eq!(escape_field("src/main.rs", Separator::Comma), "src/main.rs");
+        assert_eq!(escape_field("a,b.rs", Separator::Comma), "\"a,b.rs\"");
+        assert_eq!(escape_field("say \"hi\".txt", Separator::Comma), "\"say \"\"hi\"\".txt\"");
+        assert_eq!(escape_field("two\nlines", Separator::Comma), "\"two\nlines\"");
+        assert_eq!(row(&["a,b", "1", ""], Separator::Comma), "\"a,b\",1,\r\n");
+    }
+    #[test]
+    fn escape_tsv() {
+        assert_eq!(escape_field("a,b \"c\"", Separator::Tab), "a,b \"c\"");
+        assert_eq!(escape_field("tab\there\\\n", Separator::Tab), "tab\\there\\\\\\n");
+        assert_eq!(row(&["a\tb", "1"], Separator::Tab), "a\\tb\t1\n");
+    }
+    #[test]
+    fn columns_follow_config() {
+        let names = |config: &Config| Column::for_config(config).iter().map(|c| c.name()).collect::<Vec<_>>();
+        assert_eq!(names(&Config::default()), ["path", "language", "encoding", "size", "lines", "error"]);
+        let config = Config { classify_lines: true, long_line_limit: Some(80), ..Config::default() };
+        assert_eq!(names(&config), ["path", "language", "encoding", "size", "lines", "code", "comments", "blank", "long_lines", "error"]);
+    }
+    #[test]
+    fn totals_row_is_marked() {
+        let totals = Totals { files: 2, lines: 30, size: 400, ..Totals::default() };
+        let count = FileCount { lines: 30, ..FileCount::default() };
+        assert_eq!(Column::RowType.value("TOTAL", &count), "file");
+        assert_eq!(Column::Path.value("TOTAL", &count), "TOTAL");
+        //the totals have no path, so they can't be mistaken for a file named TOTAL
+        assert_eq!(Column::RowType.total(&totals), "total");
+        assert_eq!(Column::Path.total(&totals), "");
+        assert_eq!(Column::Lines.total(&totals), "30");
+    }
+    #[test]
+    fn uncounted_files_have_a_row() {
+        let config = Config::default();
+        let mut columns = Column::for_config(&config);
+        columns.insert(0, Column::RowType);
+        let report = DelimitedReport { config: &config, columns, separator: Separator::Comma };
+        assert_eq!(report.uncounted_row("binary", "a.png", ""), "binary,a.png,,,,,\r\n");
+        assert_eq!(report.uncounted_row("error", "b.txt", "denied"), "error,b.txt,,,,,denied\r\n");
+    }
+    #[test]
+    fn totals_add_up_measurements() {
+        let mut totals = Totals::default();
+        for (words, max_line_bytes) in [(3, 10), (4, 25), (5, 7)] {
+            let measurements = Measurements { words: Some(words), max_line_bytes: Some(max_line_bytes), ..Measurements::default() };
+            totals.add(&FileCount { measurements, ..FileCount::default() });
+        }
+        assert_eq!(Column::Words.total(&totals), "12");
+        assert_eq!(Column::MaxLineBytes.total(&totals), "25");
+        assert_eq!(Column::Chars.total(&totals), ""); //never measured
+    }
+}
@@ -0,0 +1,310 @@
+use std::{error::Error, fmt};
+
+/**
+ * text encodings that files can be read as
+ */
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    #[default]
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    Utf32Le,
+    Utf32Be,
+    Latin1,
+    Windows1252,
+}
+impl Encoding {
+    /**
+     * finds an encoding by name, ignoring case, dashes, and underscores, so "UTF-16LE", "utf16le", and "latin-1" all work
+     */
+    pub fn from_name(name: &str) -> Option<Encoding> {
+        let name: String = name.chars().filter(|c| *c != '-' && *c != '_').collect::<String>().to_ascii_lowercase();
+        match name.as_str() {
+            "utf8" => return Some(Encoding::Utf8),
+            "utf16le" => return Some(Encoding::Utf16Le),
+            "utf16be" => return Some(Encoding::Utf16Be),
+            "utf32le" => return Some(Encoding::Utf32Le),
+            "utf32be" => return Some(Encoding::Utf32Be),
+            "latin1" | "iso88591" => return Some(Encoding::Latin1),
+            "windows1252" | "cp1252" => return Some(Encoding::Windows1252),
+            _ => return None,
+        }
+    }
+
+    /**
+     * checks if '\n' is a single 0x0A byte in this encoding, and that byte is never part of another character,
+     * so lines can be counted without decoding anything
+     */
+    pub fn is_ascii_compatible(&self) -> bool {
+        return matches!(self, Encoding::Utf8 | Encoding::Latin1 | Encoding::Windows1252);
+    }
+
+    /**
+     * checks if every byte is a character on its own in this encoding, so no bytes are ever invalid
+     */
+    pub fn is_single_byte(&self) -> bool {
+        return matches!(self, Encoding::Latin1 | Encoding::Windows1252);
+    }
+}
+impl fmt::Display for Encoding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Encoding::Utf8 => write!(f, "UTF-8"),
+            Encoding::Utf16Le => write!(f, "UTF-16LE"),
+            Encoding::Utf16Be => write!(f, "UTF-16BE"),
+            Encoding::Utf32Le => write!(f, "UTF-32LE"),
+            Encoding::Utf32Be => write!(f, "UTF-32BE"),
+            Encoding::Latin1 => write!(f, "ISO-8859-1"),
+            Encoding::Windows1252 => write!(f, "Windows-1252"),
+        }
+    }
+}
+
+/**
+ * how to decode files that don't start with a byte order mark
+ */
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct DecodeOptions {
+    pub fallback: Option<Encoding>, //used for files that aren't valid UTF-8
+    pub lossy: bool, //replace anything that can't be decoded with U+FFFD instead of failing
+}
+
+/**
+ * the bytes couldn't be decoded as the encoding they were detected as
+ */
+#[derive(Debug, PartialEq, Eq)]
+pub struct DecodeError {
+    pub encoding: Encoding,
+}
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "not valid {}", self.encoding)
+    }
+}
+impl Error for DecodeError {}
+
+//byte order marks, UTF-32LE has to be checked before UTF-16LE since it starts with the same bytes
+const BOMS: [(&[u8], Encoding); 5] = [
+    (&[0xEF, 0xBB, 0xBF], Encoding::Utf8),
+    (&[0xFF, 0xFE, 0x00, 0x00], Encoding::Utf32Le),
+    (&[0x00, 0x00, 0xFE, 0xFF], Encoding::Utf32Be),
+    (&[0xFF, 0xFE], Encoding::Utf16Le),
+    (&[0xFE, 0xFF], Encoding::Utf16Be),
+];
+
+//what bytes 0x80 to 0x9F mean in Windows-1252, the rest of it is the same as Latin-1
+const WINDOWS_1252_HIGH: [char; 32] = [
+    '\u{20AC}', '\u{FFFD}', '\u{201A}', '\u{0192}', '\u{201E}', '\u{2026}', '\u{2020}', '\u{2021}',
+    '\u{02C6}', '\u{2030}', '\u{0160}', '\u{2039}', '\u{0152}', '\u{FFFD}', '\u{017D}', '\u{FFFD}',
+    '\u{FFFD}', '\u{2018}', '\u{2019}', '\u{201C}', '\u{201D}', '\u{2022}', '\u{2013}', '\u{2014}',
+    '\u{02DC}', '\u{2122}', '\u{0161}', '\u{203A}', '\u{0153}', '\u{FFFD}', '\u{017E}', '\u{0178}',
+];
+
+/**
+ * finds the byte order mark at the start of bytes, if there is one
+ * returns the encoding it marks, and how long it is
+ */
+pub fn detect_bom(bytes: &[u8]) -> Option<(Encoding, usize)> {
+    return BOMS.iter().find(|(bom, _)| bytes.starts_with(bom)).map(|(bom, encoding)| (*encoding, bom.len()));
+}
+
+/**
+ * checks if bytes look like UTF-16 text without a byte order mark, in the given byte order
+ * text that's mostly ASCII has a NUL as every other byte, the high byte of each character, and few NULs anywhere else
+ * NULs are valid UTF-8, so this has to be checked before deciding text is UTF-8
+ */
+pub fn looks_like_utf16(bytes: &[u8], encoding: Encoding) -> bool {
+    let high_byte = match encoding {
+        Encoding::Utf16Le => 1,
+        Encoding::Utf16Be => 0,
+        _ => return false,
+    };
+    let units = bytes.len() / 2;
+    if units == 0 {
+        return false;
+    }
+    let (mut high_nuls, mut low_nuls) = (0, 0);
+    for pair in bytes.chunks_exact(2) {
+        if pair[high_byte] == 0 {
+            high_nuls += 1;
+        }
+        if pair[1 - high_byte] == 0 {
+            low_nuls += 1;
+        }
+    }
+    return high_nuls * 2 >= units && low_nuls * 10 < units;
+}
+
+//checks if bytes without a byte order mark are in the fallback encoding because they match its NUL pattern
+fn matches_wide_fallback(bytes: &[u8], options: &DecodeOptions) -> Option<Encoding> {
+    return options.fallback.filter(|fallback| looks_like_utf16(bytes, *fallback));
+}
+
+/**
+ * guesses the encoding of a file from the first few bytes of it, the same way decode picks one,
+ * without having to read or decode the whole file
+ */
+pub fn sniff(head: &[u8], options: &DecodeOptions) -> Encoding {
+    if let Some((encoding, _)) = detect_bom(head) {
+        return encoding;
+    }
+    if let Some(fallback) = matches_wide_fallback(head, options) {
+        return fallback;
+    }
+    if is_utf8_prefix(head) {
+        return Encoding::Utf8;
+    }
+    return options.fallback.unwrap_or(Encoding::Utf8);
+}
+
+/**
+ * checks if bytes could be the start of valid UTF-8 text,
+ * a character cut off at the very end doesn't count as invalid, since the rest of it could come next
+ */
+pub fn is_utf8_prefix(bytes: &[u8]) -> bool {
+    match std::str::from_utf8(bytes) {
+        Ok(_) => return true,
+        Err(e) => return e.error_len().is_none(),
+    }
+}
+
+/**
+ * decodes the contents of a file
+ * files with a byte order mark are decoded as whatever it marks, others as UTF-8,
+ * or as options.fallback if they aren't valid UTF-8, or if it's UTF-16 and they look like UTF-16
+ * returns the text, without the byte order mark, and the encoding it was decoded as
+ */
+pub fn decode(bytes: &[u8], options: &DecodeOptions) -> Result<(String, Encoding), DecodeError> {
+    let (encoding, bom_length) = match detect_bom(bytes) {
+        Some(bom) => bom,
+        None if matches_wide_fallback(bytes, options).is_some() => (options.fallback.unwrap_or_default(), 0),
+        None => match std::str::from_utf8(bytes) {
+            Ok(text) => return Ok((text.to_string(), Encoding::Utf8)),
+            Err(_) => (options.fallback.unwrap_or(Encoding::Utf8), 0),
+        },
+    };
+    let text = decode_as(&bytes[bom_length..], encoding, options.lossy).ok_or(DecodeError { encoding })?;
+    return Ok((text, encoding));
+}
+
+//decodes bytes as a specific encoding, returns None if they aren't valid and lossy isn't set
+fn decode_as(bytes: &[u8], encoding: Encoding, lossy: bool) -> Option<String> {
+    match encoding {
+        Encoding::Utf8 if lossy => return Some(String::from_utf8_lossy(bytes).into_owned()),
+        Encoding::Utf8 => return String::from_utf8(bytes.to_vec()).ok(),
+        Encoding::Utf16Le | Encoding::Utf16Be => {
+            let units = bytes.chunks(2).map(|pair| match (pair, encoding) {
+                ([low, high], Encoding::Utf16Le) => Some(u16::from_le_bytes([*low, *high])),
+                ([high, low], _) => Some(u16::from_be_bytes([*high, *low])),
+                _ => None, //an odd byte left over at the end
+            });
+            let mut text = String::with_capacity(bytes.len() / 2);
+            for c in char::decode_utf16(units.map_while(|unit| unit)) {
+                text.push(match c {
+                    Ok(c) => c,
+                    Err(_) if lossy => char::REPLACEMENT_CHARACTER,
+                    Err(_) => return None,
+                });
+            }
+            if !bytes.len().is_multiple_of(2) {
+                if !lossy {
+                    return None;
+                }
+                text.push(char::REPLACEMENT_CHARACTER);
+            }
+            return Some(text);
+        }
+        Encoding::Utf32Le | Encoding::Utf32Be => {
+            let mut text = String::with_capacity(bytes.len() / 4);
+            for quad in bytes.chunks(4) {
+                let c = match (<[u8; 4]>::try_from(quad), encoding) {
+                    (Ok(quad), Encoding::Utf32Le) => char::from_u32(u32::from_le_bytes(quad)),
+                    (Ok(quad), _) => char::from_u32(u32::from_be_bytes(quad)),
+                    (Err(_), _) => None,
+                };
+                match c {
+                    Some(c) => text.push(c),
+                    None if lossy => text.push(char::REPLACEMENT_CHARACTER),
+                    None => return None,
+                }
+            }
+            return Some(text);
+        }
+        //every byte is a valid Latin-1 character
+        Encoding::Latin1 => return Some(bytes.iter().map(|b| *b as char).collect()),
+        Encoding::Windows1252 => return Some(bytes.iter().map(|b| match b {
+            0x80..=0x9F => WINDOWS_1252_HIGH[(b - 0x80) as usize],
+            _ => *b as char,
+        }).collect()),
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_utf8() {
+        assert_eq!(decode(b"plain\n", &DecodeOptions::default()), Ok(("plain\n".to_string(), Encoding::Utf8)));
+        assert_eq!(decode(b"\xEF\xBB\xBFbom\n", &DecodeOptions::default()), Ok(("bom\n".to_string(), Encoding::Utf8)));
+        assert_eq!(decode(b"caf\xE9\n", &DecodeOptions::default()), Err(DecodeError { encoding: Encoding::Utf8 }));
+    }
+    #[test]
+    fn decode_utf16_and_utf32_boms() {
+        assert_eq!(decode(b"\xFF\xFEh\x00i\x00\n\x00", &DecodeOptions::default()), Ok(("hi\n".to_string(), Encoding::Utf16Le)));
+        assert_eq!(decode(b"\xFE\xFF\x00h\x00i\x00\n", &DecodeOptions::default()), Ok(("hi\n".to_string(), Encoding::Utf16Be)));
+        assert_eq!(decode(b"\xFF\xFE\x00\x00h\x00\x00\x00\n\x00\x00\x00", &DecodeOptions::default()), Ok(("h\n".to_string(), Encoding::Utf32Le)));
+        assert_eq!(decode(b"\x00\x00\xFE\xFF\x00\x00\x00h", &DecodeOptions::default()), Ok(("h".to_string(), Encoding::Utf32Be)));
+        //a lone surrogate
+        assert_eq!(decode(b"\xFF\xFE\x00\xD8", &DecodeOptions::default()), Err(DecodeError { encoding: Encoding::Utf16Le }));
+    }
+    #[test]
+    fn decode_with_fallback() {
+        let options = DecodeOptions { fallback: Some(Encoding::Latin1), lossy: false };
+        assert_eq!(decode(b"caf\xE9\n", &options), Ok(("caf\u{e9}\n".to_string(), Encoding::Latin1)));
+        //valid UTF-8 doesn't use the fallback
+        assert_eq!(decode("caf\u{e9}\n".as_bytes(), &options), Ok(("caf\u{e9}\n".to_string(), Encoding::Utf8)));
+
+        let options = DecodeOptions { fallback: Some(Encoding::Windows1252), lossy: false };
+        assert_eq!(decode(b"\x93quoted\x94", &options), Ok(("\u{201C}quoted\u{201D}".to_string(), Encoding::Windows1252)));
+    }
+    #[test]
+    fn decode_lossy() {
+        let options = DecodeOptions { fallback: None, lossy: true };
+        assert_eq!(decode(b"caf\xE9\n", &options), Ok(("caf\u{FFFD}\n".to_string(), Encoding::Utf8)));
+        assert_eq!(decode(b"\xFF\xFEh\x00i", &options), Ok(("h\u{FFFD}".to_string(), Encoding::Utf16Le)));
+    }
+    #[test]
+    fn sniff_encodings() {
+        let options = DecodeOptions { fallback: Some(Encoding::Latin1), lossy: false };
+        assert_eq!(sniff(b"\xFF\xFEh\x00", &options), Encoding::Utf16Le);
+        assert_eq!(sniff(b"plain", &options), Encoding::Utf8);
+        assert_eq!(sniff(b"cut off \xE4\xB8", &options), Encoding::Utf8);
+        assert_eq!(sniff(b"caf\xE9 ", &options), Encoding::Latin1);
+        assert_eq!(sniff(b"caf\xE9 ", &DecodeOptions::default()), Encoding::Utf8);
+    }
+    #[test]
+    fn utf16_without_bom() {
+        let utf16le: Vec<u8> = "fn main() {}\n".encode_utf16().flat_map(|unit| unit.to_le_bytes()).collect();
+        let options = DecodeOptions { fallback: Some(Encoding::Utf16Le), lossy: false };
+        assert!(looks_like_utf16(&utf16le, Encoding::Utf16Le));
+        assert!(!looks_like_utf16(&utf16le, Encoding::Utf16Be));
+        assert!(!looks_like_utf16(b"plain ascii", Encoding::Utf16Le));
+        //NULs are valid UTF-8, so without the fallback it's read as UTF-8
+        assert_eq!(sniff(&utf16le, &DecodeOptions::default()), Encoding::Utf8);
+        assert_eq!(sniff(&utf16le, &options), Encoding::Utf16Le);
+        assert_eq!(decode(&utf16le, &options), Ok(("fn main() {}\n".to_string(), Encoding::Utf16Le)));
+        //UTF-8 text doesn't look like UTF-16, so the fallback isn't used for it
+        assert_eq!(decode(b"plain\n", &options), Ok(("plain\n".to_string(), Encoding::Utf8)));
+    }
+    #[test]
+    fn encoding_names() {
+        assert_eq!(Encoding::from_name("latin-1"), Some(Encoding::Latin1));
+        assert_eq!(Encoding::from_name("UTF_16LE"), Some(Encoding::Utf16Le));
+        assert_eq!(Encoding::from_name("cp1252"), Some(Encoding::Windows1252));
+        assert_eq!(Encoding::from_name("ebcdic"), None);
+    }
+}
@@ -0,0 +1,231 @@
+/**
+ * checks if text matches a shell style glob pattern
+ * supports * (any number of characters), ? (any one character), and [...] character classes like [abc], [a-z], and [!0-9]
+ */
+pub fn glob_matches(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    return matches_from(&pattern, &text);
+}
+
+/**
+ * checks if a '/' separated path matches a glob pattern, where * and ? never match a '/'
+ * a "**" component matches any number of directories, so a pattern starting with one matches at any depth,
+ * and a pattern ending with one matches everything inside a directory
+ */
+pub fn glob_matches_path(pattern: &str, path: &str) -> bool {
+    let pattern: Vec<&str> = pattern.split('/').collect();
+    let path: Vec<&str> = path.split('/').collect();
+    return components_match(&pattern, &path);
+}
+
+/**
+ * a glob that's matched against paths relative to some directory, like the lines of a .gitignore
+ * patterns with a '/' before the end are anchored to that directory, others match the file name at any depth,
+ * and a trailing '/' means the pattern only matches directories
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct PathPattern {
+    pattern: String,
+    anchored: bool,
+    directory_only: bool,
+}
+impl PathPattern {
+    /**
+     * parses a pattern, returns None if there's nothing left of it after removing slashes
+     */
+    pub fn new(pattern: &str) -> Option<PathPattern> {
+        let directory_only = pattern.ends_with('/');
+        let pattern = pattern.trim_end_matches('/');
+        if pattern.is_empty() {
+            return None;
+        }
+        let anchored = pattern.contains('/');
+        let pattern = pattern.strip_prefix('/').unwrap_or(pattern).to_string();
+        return Some(PathPattern { pattern, anchored, directory_only });
+    }
+
+    /**
+     * checks if a '/' separated path, relative to the directory the pattern is anchored to, matches the pattern
+     */
+    pub fn matches(&self, relative_path: &str, is_dir: bool) -> bool {
+        if self.directory_only && !is_dir {
+            return false;
+        }
+        if self.anchored {
+            return glob_matches_path(&self.pattern, relative_path);
+        }
+        let file_name = relative_path.rsplit('/').next().unwrap_or(relative_path);
+        return glob_matches(&self.pattern, file_name);
+    }
+}
+
+//matches pattern components against path components one at a time
+fn components_match(pattern: &[&str], path: &[&str]) -> bool {
+    match pattern.first() {
+        None => return path.is_empty(),
+        //a trailing ** only matches things inside the directory, not the directory itself
+        Some(&"**") if pattern.len() == 1 => return !path.is_empty(),
+        Some(&"**") => return (0..=path.len()).any(|skipped| components_match(&pattern[1..], &path[skipped..])),
+        Some(component) => return !path.is_empty() && glob_matches(component, path[0]) && components_match(&pattern[1..], &path[1..]),
+    }
+}
+
+//matches pattern against text, backtracking to the most recent * when a match fails
+fn matches_from(pattern: &[char], text: &[char]) -> bool {
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None; //(position of the last *, position in text it's matched up to)
+
+    while t < text.len() {
+        if p < pattern.len() {
+            match pattern[p] {
+                '*' => {
+                    backtrack = Some((p, t));
+                    p += 1;
+                    continue;
+                }
+                '?' => {
+                    p += 1;
+                    t += 1;
+                    continue;
+                }
+                '[' => {
+                    if let Some((matched, class_len)) = match_class(&pattern[p..], text[t]) {
+                        if matched {
+                            p += class_len;
+                            t += 1;
+                            continue;
+                        }
+                    } else if text[t] == '[' {
+                        //an unclosed [ is just a normal character
+                        p += 1;
+                        t += 1;
+                        continue;
+                    }
+                }
+                '\\' if p + 1 < pattern.len() => {
+                    if pattern[p + 1] == text[t] {
+                        p += 2;
+                        t += 1;
+                        continue;
+                    }
+                }
+                c => {
+                    if c == text[t] {
+                        p += 1;
+                        t += 1;
+                        continue;
+                    }
+                }
+            }
+        }
+        //mismatch, let the last * eat one more character and try again
+        match backtrack {
+            Some((star, matched_to)) => {
+                p = star + 1;
+                t = matched_to + 1;
+                backtrack = Some((star, matched_to + 1));
+            }
+            None => return false,
+        }
+    }
+
+    //any leftover pattern has to be all *'s
+    return pattern[p..].iter().all(|c| *c == '*');
+}
+
+/**
+ * checks if c is in the character class at the start of pattern
+ * returns whether it matched and how many pattern characters the class used, or None if the class is never closed
+ */
+fn match_class(pattern: &[char], c: char) -> Option<(bool, usize)> {
+    let mut i = 1; //skip the [
+    let negated = matches!(pattern.get(i), Some('!') | Some('^'));
+    if negated {
+        i += 1;
+    }
+    let mut matched = false;
+    let mut first = true;
+    while i < pattern.len() {
+        //a ] right at the start is part of the class, not the end of it
+        if pattern[i] == ']' && !first {
+            return Some((matched != negated, i + 1));
+        }
+        first = false;
+        if i + 2 < pattern.len() && pattern[i + 1] == '-' && pattern[i + 2] != ']' {
+            if pattern[i] <= c && c <= pattern[i + 2] {
+                matched = true;
+            }
+            i += 3;
+        } else {
+            if pattern[i] == c {
+                matched = true;
+            }
+            i += 1;
+        }
+    }
+    return None;
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn glob_literal() {
+        assert!(glob_matches("Makefile", "Makefile"));
+        assert!(!glob_matches("Makefile", "makefile"));
+        assert!(!glob_matches("Makefile", "Makefile.am"));
+    }
+    #[test]
+    fn glob_wildcards() {
+        assert!(glob_matches("*.rs", "lib.rs"));
+        assert!(glob_matches("*", ""));
+        assert!(glob_matches("Docker*", "Dockerfile"));
+        assert!(glob_matches("*file*", "Makefile.in"));
+        assert!(glob_matches("?at", "cat"));
+        assert!(!glob_matches("?at", "at"));
+        assert!(!glob_matches("*.rs", "lib.rs.bak"));
+    }
+    #[test]
+    fn glob_character_classes() {
+        assert!(glob_matches("file[0-9]", "file7"));
+        assert!(!glob_matches("file[0-9]", "filex"));
+        assert!(glob_matches("file[!0-9]", "filex"));
+        assert!(glob_matches("[]]", "]"));
+        assert!(glob_matches("[ab].txt", "b.txt"));
+        assert!(glob_matches("a[b", "a[b"));
+    }
+    #[test]
+    fn glob_paths() {
+        assert!(glob_matches_path("src/*.rs", "src/lib.rs"));
+        assert!(!glob_matches_path("src/*.rs", "src/bin/main.rs"));
+        assert!(glob_matches_path("**/target", "target"));
+        assert!(glob_matches_path("**/target", "a/b/target"));
+        assert!(glob_matches_path("vendor/**", "vendor/a/b.c"));
+        assert!(!glob_matches_path("vendor/**", "vendor"));
+        assert!(glob_matches_path("a/**/b", "a/b"));
+        assert!(glob_matches_path("a/**/b", "a/x/y/b"));
+        assert!(!glob_matches_path("a/**/b", "a/x/y/c"));
+    }
+    #[test]
+    fn path_patterns() {
+        let pattern = PathPattern::new("vendor/").unwrap();
+        assert!(pattern.matches("vendor", true));
+        assert!(pattern.matches("third_party/vendor", true));
+        assert!(!pattern.matches("vendor", false));
+
+        let pattern = PathPattern::new("/docs/*.md").unwrap();
+        assert!(pattern.matches("docs/index.md", false));
+        assert!(!pattern.matches("src/docs/index.md", false));
+
+        let pattern = PathPattern::new("*.[ch]").unwrap();
+        assert!(pattern.matches("src/main.c", false));
+        assert!(pattern.matches("include/main.h", false));
+        assert!(!pattern.matches("src/main.rs", false));
+
+        assert_eq!(PathPattern::new("/"), None);
+    }
+}
@@ -0,0 +1,253 @@
+use std::{collections::BTreeMap, path::Path};
+
+use crate::{
+    delimited::Column,
+    report::{Report, Totals},
+    Config, FileCount,
+};
+
+//how wide the longest bar of a chart is, in pixels
+const CHART_WIDTH: usize = 400;
+//how much room the labels left of the bars get, in pixels
+const LABEL_WIDTH: usize = 240;
+const BAR_HEIGHT: usize = 18;
+const BAR_GAP: usize = 4;
+//the colors of the bars, one after the other
+const PALETTE: [&str; 8] = ["#4e79a7", "#f28e2b", "#e15759", "#76b7b2", "#59a14f", "#edc948", "#b07aa1", "#9c755f"];
+
+const STYLE: &str = "\
+body { font-family: sans-serif; margin: 2em; color: #222; }
+table { border-collapse: collapse; margin-bottom: 2em; }
+th, td { border: 1px solid #ccc; padding: 0.25em 0.6em; }
+th { background: #f3f3f3; cursor: pointer; user-select: none; }
+td.number { text-align: right; font-variant-numeric: tabular-nums; }
+tfoot td { font-weight: bold; }
+svg text { font-size: 12px; }";
+
+//sorts a table by the column whose header was clicked, clicking it again reverses the order
+const SCRIPT: &str = "\
+document.querySelectorAll('table.sortable th').forEach(function (header, column) {
+  header.addEventListener('click', function () {
+    var body = header.closest('table').tBodies[0];
+    var ascending = header.dataset.order !== 'ascending';
+    header.closest('tr').querySelectorAll('th').forEach(function (h) { delete h.dataset.order; });
+    header.dataset.order = ascending ? 'ascending' : 'descending';
+    var rows = Array.from(body.rows);
+    rows.sort(function (a, b) {
+      var x = a.cells[column], y = b.cells[column];
+      var result = 'value' in x.dataset
+        ? Number(x.dataset.value) - Number(y.dataset.value)
+        : x.textContent.localeCompare(y.textContent);
+      return ascending ? result : -result;
+    });
+    rows.forEach(function (row) { body.appendChild(row); });
+  });
+});";
+
+/**
+ * escapes text so it shows up as is in HTML, both between tags and in attributes
+ */
+pub fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    return escaped;
+}
+
+/**
+ * draws a horizontal bar chart, with bar lengths proportional to each value
+ */
+pub fn bar_chart(title: &str, bars: &[(String, usize)]) -> String {
+    let max = bars.iter().map(|(_, value)| *value).max().unwrap_or(0).max(1);
+    let height = bars.len() * (BAR_HEIGHT + BAR_GAP);
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" role=\"img\" width=\"{}\" height=\"{}\"><title>{}</title>\n",
+        LABEL_WIDTH + CHART_WIDTH + 80, height, escape_html(title)
+    );
+    for (i, (label, value)) in bars.iter().enumerate() {
+        let y = i * (BAR_HEIGHT + BAR_GAP);
+        let width = value * CHART_WIDTH / max;
+        let text_y = y + BAR_HEIGHT - 5;
+        svg += &format!(
+            "<text x=\"{}\" y=\"{}\" text-anchor=\"end\">{}</text>\
+            <rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"><title>{}: {}</title></rect>\
+            <text x=\"{}\" y=\"{}\">{}</text>\n",
+            LABEL_WIDTH - 6, text_y, escape_html(label),
+            LABEL_WIDTH, y, width, BAR_HEIGHT, PALETTE[i % PALETTE.len()], escape_html(label), value,
+            LABEL_WIDTH + width + 4, text_y, value,
+        );
+    }
+    svg += "</svg>\n";
+    return svg;
+}
+
+//a number cell, with the value to sort by
+fn number_cell(value: usize) -> String {
+    return format!("<td class=\"number\" data-value=\"{0}\">{0}</td>", value);
+}
+
+//a cell of the table of files, numbers get the value to sort by
+fn cell(column: Column, value: &str) -> String {
+    match value.parse::<usize>() {
+        Ok(number) if column.is_number() => return number_cell(number),
+        _ => return format!("<td>{}</td>", escape_html(value)),
+    }
+}
+
+/**
+ * --format=HTML, one page with no external assets: a summary, a sortable table of every file,
+ * and tables and bar charts of the lines in each language and each directory, including its subdirectories
+ * the table of files has a column for everything config counts
+ * the table of files is left out with config.summary_only, and the summary and totals row with config.no_summary
+ */
+pub struct HtmlReport<'a> {
+    config: &'a Config,
+    columns: Vec<Column>,
+    rows: Vec<String>, //a row of the table of files, for each file
+    directories: BTreeMap<String, (usize, usize)>, //directory -> (files, lines)
+}
+impl HtmlReport<'_> {
+    pub fn new(config: &Config) -> HtmlReport<'_> {
+        //errors go to std. error instead of a column, and the encoding is only shown if it was asked for
+        let columns = Column::for_config(config).into_iter().filter(|column| match column {
+            Column::Encoding => config.show_encoding,
+            Column::Error => false,
+            _ => true,
+        }).collect();
+        return HtmlReport { config, columns, rows: Vec::new(), directories: BTreeMap::new() };
+    }
+
+    //writes the table of every file, with a totals row at the bottom
+    fn files_table(&self, page: &mut String, totals: &Totals) {
+        *page += "<h2>Files</h2>\n<table class=\"sortable\">\n<thead><tr>";
+        for column in &self.columns {
+            *page += &format!("<th>{}</th>", column.title());
+        }
+        *page += "</tr></thead>\n<tbody>\n";
+        for row in &self.rows {
+            *page += row;
+        }
+        *page += "</tbody>\n";
+        if !self.config.no_summary {
+            *page += "<tfoot><tr>";
+            for column in &self.columns {
+                match column {
+                    Column::Path => *page += "<td>Total</td>",
+                    column => *page += &cell(*column, &column.total(totals)),
+                }
+            }
+            *page += "</tr></tfoot>\n";
+        }
+        *page += "</table>\n";
+    }
+}
+impl Report for HtmlReport<'_> {
+    fn file(&mut self, path: &str, count: &FileCount) {
+        //the lines of a file count toward every directory it's in, up to the one that was searched
+        let searched = Path::new(&self.config.path);
+        for directory in Path::new(path).ancestors().skip(1) {
+            if directory.as_os_str().is_empty() {
+                break;
+            }
+            let totals = self.directories.entry(directory.to_string_lossy().into_owned()).or_default();
+            totals.0 += 1;
+            totals.1 += count.lines;
+            if directory == searched {
+                break;
+            }
+        }
+
+        let mut row = String::from("<tr>");
+        for column in &self.columns {
+            match column {
+                Column::Language => row += &cell(*column, count.language.map_or("Unknown", |l| l.name)),
+                column => row += &cell(*column, &column.value(path, count)),
+            }
+        }
+        row += "</tr>\n";
+        self.rows.push(row);
+    }
+
+    fn finish(&mut self, totals: &Totals) {
+        let title = format!("Line counts of {}", self.config.path);
+        let mut page = format!(
+            "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>{0}</title>\n<style>\n{1}\n</style>\n</head>\n<body>\n<h1>{0}</h1>\n",
+            escape_html(&title), STYLE
+        );
+        if !self.config.no_summary {
+            page += &format!("<p>{}, {} bytes</p>\n", escape_html(&totals.summary()), totals.size);
+        }
+
+        //every file
+        if !self.config.summary_only {
+            self.files_table(&mut page, totals);
+        }
+
+        //lines by language, and by directory, biggest first in the charts
+        let summaries = [
+            ("Languages", "Language", totals.languages.iter().map(|(name, counts)| (name.to_string(), *counts)).collect::<Vec<_>>()),
+            ("Directories", "Directory", self.directories.iter().map(|(name, counts)| (name.clone(), *counts)).collect()),
+        ];
+        for (heading, column, summary) in summaries {
+            if summary.is_empty() {
+                continue;
+            }
+            page += &format!("<h2>{}</h2>\n", heading);
+            let mut bars: Vec<(String, usize)> = summary.iter().map(|(name, (_, lines))| (name.clone(), *lines)).collect();
+            bars.sort_by_key(|(_, lines)| std::cmp::Reverse(*lines));
+            page += &bar_chart(&format!("Lines by {}", column.to_lowercase()), &bars);
+            page += &format!("<table class=\"sortable\">\n<thead><tr><th>{}</th><th>Files</th><th>Lines</th></tr></thead>\n<tbody>\n", column);
+            for (name, (files, lines)) in &summary {
+                page += &format!("<tr><td>{}</td>{}{}</tr>\n", escape_html(name), number_cell(*files), number_cell(*lines));
+            }
+            page += "</tbody>\n</table>\n";
+        }
+
+        page += &format!("<script>\n{}\n</script>\n</body>\n</html>", SCRIPT);
+        println!("{}", page);
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escape() {
+        assert_eq!(escape_html("<a href=\"x\">Tom & Jerry's</a>"), "&lt;a href=&quot;x&quot;&gt;Tom &amp; Jerry&#39;s&lt;/a&gt;");
+    }
+    #[test]
+    fn directories_include_subdirectories() {
+        let config = Config { path: "src".to_string(), path_is_directory: true, classify_lines: true, ..Config::default() };
+        let mut report = HtmlReport::new(&config);
+        let count = |lines| FileCount { lines, ..FileCount::default() };
+        report.file("src/main.rs", &count(10));
+        report.file("src/a/lib.rs", &count(20));
+        report.file("src/a/b/deep.rs", &count(30));
+
+        assert_eq!(report.directories["src"], (3, 60));
+        assert_eq!(report.directories["src/a"], (2, 50));
+        assert_eq!(report.directories["src/a/b"], (1, 30));
+        assert_eq!(report.directories.len(), 3);
+        assert_eq!(report.columns, [Column::Path, Column::Language, Column::Size, Column::Lines, Column::Code, Column::Comments, Column::Blank]);
+    }
+    #[test]
+    fn chart_bars_are_proportional() {
+        let chart = bar_chart("Lines", &[("big".to_string(), 200), ("<small>".to_string(), 50)]);
+        assert!(chart.contains(&format!("width=\"{}\"", CHART_WIDTH)));
+        assert!(chart.contains(&format!("width=\"{}\"", CHART_WIDTH / 4)));
+        assert!(chart.contains("&lt;small&gt;"));
+        //no bars, and nothing divided by zero
+        assert!(bar_chart("Lines", &[("empty".to_string(), 0)]).contains("width=\"0\""));
+    }
+}
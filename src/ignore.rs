@@ -0,0 +1,267 @@
+use std::{
+    env,
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use crate::glob::PathPattern;
+
+//files in each directory that list paths to ignore, in order of increasing priority
+const IGNORE_FILE_NAMES: [&str; 2] = [".gitignore", ".ignore"];
+
+//a single line of an ignore file
+#[derive(Debug, PartialEq)]
+struct Rule {
+    pattern: PathPattern,
+    negated: bool, //starts with !, so it un-ignores anything it matches
+}
+
+//the rules from one ignore file, along with where the directory it's in is, relative to the repository root
+#[derive(Debug)]
+struct RuleSet {
+    directory: String,
+    rules: Vec<Rule>,
+}
+
+/**
+ * a stack of gitignore style rules, that grows as a walk enters directories
+ * rules from deeper directories, and later lines, take priority over earlier ones
+ * cloning it is cheap, since the rule sets themselves are shared, so every directory in a walk can have its own copy
+ */
+#[derive(Debug, Clone)]
+pub struct IgnoreRules {
+    sets: Vec<Arc<RuleSet>>,
+    root_prefix: String, //where the walk root is relative to the repository root, ends in / unless empty
+}
+impl IgnoreRules {
+    /**
+     * loads the rules that apply to a walk starting at root:
+     * core.excludesFile, .git/info/exclude, and the ignore files in every directory from the repository root down to root's parent
+     * root's own ignore files are loaded by push_directory, like any other directory in the walk
+     */
+    pub fn for_root(root: &Path) -> IgnoreRules {
+        let mut ignore_rules = IgnoreRules { sets: Vec::new(), root_prefix: String::new() };
+        let root = match fs::canonicalize(root) {
+            Ok(r) => r,
+            Err(_) => return ignore_rules,
+        };
+        let repository_root = match root.ancestors().find(|dir| dir.join(".git").exists()) {
+            Some(dir) => dir,
+            None => return ignore_rules, //not in a repository, only the ignore files inside the walk apply
+        };
+        let git_dir = find_git_dir(repository_root);
+
+        //global excludes, then the repository's own excludes
+        if let Some(excludes_file) = find_excludes_file(git_dir.as_deref()) {
+            ignore_rules.push_file(&excludes_file, String::new());
+        }
+        if let Some(git_dir) = &git_dir {
+            ignore_rules.push_file(&git_dir.join("info").join("exclude"), String::new());
+        }
+
+        //ignore files in the directories above root, starting from the repository root
+        let relative_root = root.strip_prefix(repository_root).unwrap_or(Path::new(""));
+        let mut directory = repository_root.to_path_buf();
+        let mut directory_prefix = String::new();
+        for component in relative_root.components() {
+            for name in IGNORE_FILE_NAMES {
+                ignore_rules.push_file(&directory.join(name), directory_prefix.clone());
+            }
+            directory.push(component);
+            directory_prefix.push_str(&component.as_os_str().to_string_lossy());
+            directory_prefix.push('/');
+        }
+        ignore_rules.root_prefix = directory_prefix;
+        return ignore_rules;
+    }
+
+    /**
+     * loads the ignore files in a directory the walk is entering
+     * relative_path is where the directory is relative to the walk root, using / as the separator
+     */
+    pub fn push_directory(&mut self, directory: &Path, relative_path: &str) {
+        let mut prefix = self.root_prefix.clone();
+        if !relative_path.is_empty() {
+            prefix.push_str(relative_path);
+            prefix.push('/');
+        }
+        for name in IGNORE_FILE_NAMES {
+            self.push_file(&directory.join(name), prefix.clone());
+        }
+    }
+
+    /**
+     * checks if a path should be ignored
+     * relative_path is where the path is relative to the walk root, using / as the separator
+     */
+    pub fn is_ignored(&self, relative_path: &str, is_dir: bool) -> bool {
+        let full_path = format!("{}{}", self.root_prefix, relative_path);
+        let mut ignored = false;
+
+        for set in &self.sets {
+            let path_in_set = match full_path.strip_prefix(&set.directory) {
+                Some(p) => p,
+                None => continue,
+            };
+            for rule in &set.rules {
+                if rule.pattern.matches(path_in_set, is_dir) {
+                    ignored = !rule.negated;
+                }
+            }
+        }
+        return ignored;
+    }
+
+    //reads an ignore file and adds its rules, if it exists and has any
+    fn push_file(&mut self, path: &Path, directory: String) {
+        if let Ok(contents) = fs::read_to_string(path) {
+            let rules: Vec<Rule> = contents.lines().filter_map(parse_rule).collect();
+            if !rules.is_empty() {
+                self.sets.push(Arc::new(RuleSet { directory, rules }));
+            }
+        }
+    }
+}
+
+//parses one line of an ignore file, returns None for blank lines and comments
+fn parse_rule(line: &str) -> Option<Rule> {
+    //trailing spaces are ignored unless they're escaped with a backslash
+    let mut line = line.trim_end_matches('\r');
+    while line.ends_with(' ') && !line.ends_with("\\ ") {
+        line = &line[..line.len() - 1];
+    }
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+
+    let negated = line.starts_with('!');
+    //drop the ! of a negation, or the backslash of an escaped \! or \#
+    if negated || line.starts_with("\\!") || line.starts_with("\\#") {
+        line = &line[1..];
+    }
+    let pattern = PathPattern::new(&line.replace("\\ ", " "))?;
+
+    return Some(Rule { pattern, negated });
+}
+
+//finds the git directory of a repository, following the "gitdir:" line if .git is a file, like in worktrees and submodules
+fn find_git_dir(repository_root: &Path) -> Option<PathBuf> {
+    let dot_git = repository_root.join(".git");
+    if dot_git.is_dir() {
+        return Some(dot_git);
+    }
+    let contents = fs::read_to_string(&dot_git).ok()?;
+    let git_dir = contents.lines().find_map(|line| line.strip_prefix("gitdir:"))?.trim();
+    return Some(repository_root.join(git_dir));
+}
+
+//finds the file core.excludesFile points to, or git's default if it isn't set
+fn find_excludes_file(git_dir: Option<&Path>) -> Option<PathBuf> {
+    let home = env::var_os("HOME").map(PathBuf::from);
+    let xdg_config = env::var_os("XDG_CONFIG_HOME").map(PathBuf::from).or_else(|| home.as_ref().map(|h| h.join(".config")));
+
+    //later config files override earlier ones
+    let mut config_files = Vec::new();
+    if let Some(xdg_config) = &xdg_config {
+        config_files.push(xdg_config.join("git").join("config"));
+    }
+    match env::var_os("GIT_CONFIG_GLOBAL") {
+        Some(global) => config_files.push(PathBuf::from(global)),
+        None => if let Some(home) = &home { config_files.push(home.join(".gitconfig")); },
+    }
+    if let Some(git_dir) = git_dir {
+        config_files.push(git_dir.join("config"));
+    }
+
+    let configured = config_files.iter().rev()
+        .filter_map(|file| fs::read_to_string(file).ok())
+        .filter_map(|contents| read_excludes_file_setting(&contents))
+        .next(); //searching backwards, so the last config file that sets it wins
+    return match configured {
+        Some(path) => match (path.strip_prefix("~/"), &home) {
+            (Some(rest), Some(home)) => Some(home.join(rest)),
+            _ => Some(PathBuf::from(path)),
+        },
+        None => xdg_config.map(|c| c.join("git").join("ignore")),
+    };
+}
+
+//finds the value of core.excludesFile in the contents of a git config file
+fn read_excludes_file_setting(contents: &str) -> Option<String> {
+    let mut in_core = false;
+    let mut value = None;
+    for line in contents.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_core = line.trim_start_matches('[').trim_end_matches(']').trim().eq_ignore_ascii_case("core");
+        } else if in_core {
+            if let Some((key, v)) = line.split_once('=') {
+                if key.trim().eq_ignore_ascii_case("excludesfile") {
+                    value = Some(v.trim().trim_matches('"').to_string());
+                }
+            }
+        }
+    }
+    return value;
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //builds rules as if they were all in one ignore file at the root of the walk
+    fn rules_from(contents: &str) -> IgnoreRules {
+        let rules = contents.lines().filter_map(parse_rule).collect();
+        return IgnoreRules { sets: vec![Arc::new(RuleSet { directory: String::new(), rules })], root_prefix: String::new() };
+    }
+
+    #[test]
+    fn parse_rules() {
+        assert_eq!(parse_rule("# comment"), None);
+        assert_eq!(parse_rule("   "), None);
+        assert_eq!(parse_rule("!/build/"), Some(Rule { pattern: PathPattern::new("/build/").unwrap(), negated: true }));
+        assert_eq!(parse_rule("\\#file "), Some(Rule { pattern: PathPattern::new("#file").unwrap(), negated: false }));
+        assert_eq!(parse_rule("\\!important"), Some(Rule { pattern: PathPattern::new("!important").unwrap(), negated: false }));
+    }
+    #[test]
+    fn ignore_by_name_at_any_depth() {
+        let rules = rules_from("target\n*.log\n");
+        assert!(rules.is_ignored("target", true));
+        assert!(rules.is_ignored("a/b/target", true));
+        assert!(rules.is_ignored("a/debug.log", false));
+        assert!(!rules.is_ignored("src/lib.rs", false));
+    }
+    #[test]
+    fn ignore_anchored_and_directory_only() {
+        let rules = rules_from("/build\nnode_modules/\ndoc/*.txt\n");
+        assert!(rules.is_ignored("build", true));
+        assert!(!rules.is_ignored("src/build", true));
+        assert!(rules.is_ignored("web/node_modules", true));
+        assert!(!rules.is_ignored("node_modules", false));
+        assert!(rules.is_ignored("doc/notes.txt", false));
+        assert!(!rules.is_ignored("doc/sub/notes.txt", false));
+    }
+    #[test]
+    fn ignore_negation() {
+        let rules = rules_from("*.log\n!keep.log\n");
+        assert!(rules.is_ignored("debug.log", false));
+        assert!(!rules.is_ignored("keep.log", false));
+    }
+    #[test]
+    fn ignore_nested_rule_sets() {
+        let mut rules = rules_from("*.txt\n");
+        rules.sets.push(Arc::new(RuleSet { directory: "docs/".to_string(), rules: vec![parse_rule("!*.txt").unwrap(), parse_rule("/private").unwrap()] }));
+        assert!(rules.is_ignored("notes.txt", false));
+        assert!(!rules.is_ignored("docs/notes.txt", false));
+        assert!(rules.is_ignored("docs/private", true));
+        assert!(!rules.is_ignored("private", true));
+    }
+    #[test]
+    fn excludes_file_setting() {
+        let config = "[user]\n\tname = someone\n[core]\n\texcludesFile = ~/.gitignore_global\n";
+        assert_eq!(read_excludes_file_setting(config), Some("~/.gitignore_global".to_string()));
+        assert_eq!(read_excludes_file_setting("[user]\n\texcludesfile = nope\n"), None);
+    }
+}
@@ -0,0 +1,486 @@
+use std::{fmt, path::Path};
+
+use crate::{
+    parallel,
+    report::{Report, Totals},
+    walk::SymlinkPolicy,
+    Config, FileCount, LineStats, LineTerminators,
+};
+
+/**
+ * the version of the layout of JSON output, bumped whenever a field is renamed or removed, or its meaning changes
+ * adding a field doesn't change it, so consumers should ignore fields they don't know
+ */
+pub const SCHEMA_VERSION: u64 = 1;
+
+/**
+ * a JSON value, written compactly by Display, or indented by pretty
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Integer(u64),
+    Float(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>), //keeps the order fields were added in
+}
+impl Json {
+    /**
+     * makes an object out of fields
+     */
+    pub fn object<const N: usize>(fields: [(&str, Json); N]) -> Json {
+        return Json::Object(fields.into_iter().map(|(key, value)| (key.to_string(), value)).collect());
+    }
+
+    /**
+     * writes the value with each field and element on its own line, indented by two spaces for each level
+     */
+    pub fn pretty(&self) -> String {
+        let mut out = String::new();
+        self.write(&mut out, Some(0));
+        return out;
+    }
+
+    //writes the value to out, indented to the given depth, or all on one line if indent is None
+    fn write(&self, out: &mut String, indent: Option<usize>) {
+        //starts a new line at the given depth, if pretty printing
+        let new_line = |out: &mut String, depth: usize| if indent.is_some() {
+            out.push('\n');
+            out.push_str(&"  ".repeat(depth));
+        };
+        let depth = indent.unwrap_or(0);
+        match self {
+            Json::Null => out.push_str("null"),
+            Json::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+            Json::Integer(n) => out.push_str(&n.to_string()),
+            Json::Float(n) if n.is_finite() => out.push_str(&n.to_string()),
+            Json::Float(_) => out.push_str("null"), //JSON has no infinity or NaN
+            Json::String(s) => write_string(out, s),
+            Json::Array(elements) if elements.is_empty() => out.push_str("[]"),
+            Json::Array(elements) => {
+                out.push('[');
+                for (i, element) in elements.iter().enumerate() {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    new_line(out, depth + 1);
+                    element.write(out, indent.map(|d| d + 1));
+                }
+                new_line(out, depth);
+                out.push(']');
+            },
+            Json::Object(fields) if fields.is_empty() => out.push_str("{}"),
+            Json::Object(fields) => {
+                out.push('{');
+                for (i, (key, value)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    new_line(out, depth + 1);
+                    write_string(out, key);
+                    out.push_str(if indent.is_some() { ": " } else { ":" });
+                    value.write(out, indent.map(|d| d + 1));
+                }
+                new_line(out, depth);
+                out.push('}');
+            },
+        }
+    }
+}
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut out = String::new();
+        self.write(&mut out, None);
+        write!(f, "{}", out)
+    }
+}
+impl From<bool> for Json {
+    fn from(b: bool) -> Json {
+        return Json::Bool(b);
+    }
+}
+impl From<usize> for Json {
+    fn from(n: usize) -> Json {
+        return Json::Integer(n as u64);
+    }
+}
+impl From<f64> for Json {
+    fn from(n: f64) -> Json {
+        return Json::Float(n);
+    }
+}
+impl From<&str> for Json {
+    fn from(s: &str) -> Json {
+        return Json::String(s.to_string());
+    }
+}
+impl From<String> for Json {
+    fn from(s: String) -> Json {
+        return Json::String(s);
+    }
+}
+impl<T: Into<Json>> From<Option<T>> for Json {
+    fn from(value: Option<T>) -> Json {
+        return value.map_or(Json::Null, Into::into);
+    }
+}
+impl<T: Into<Json>> From<Vec<T>> for Json {
+    fn from(values: Vec<T>) -> Json {
+        return Json::Array(values.into_iter().map(Into::into).collect());
+    }
+}
+
+//writes s as a quoted JSON string, escaping anything that has to be
+fn write_string(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/**
+ * the options a run was started with, every one that changes what's counted or how
+ */
+pub fn config_record(config: &Config) -> Json {
+    let measures = &config.measures;
+    return Json::object([
+        ("path", config.path.as_str().into()),
+        ("recursive", config.search_subdirectories_recursively.into()),
+        ("filter", config.extensions_to_filter_for.clone().into()),
+        ("names", config.names_to_filter_for.clone().into()),
+        ("exclude", config.exclude_patterns.clone().into()),
+        ("include", config.include_patterns.clone().into()),
+        ("require_extension", config.require_extension.into()),
+        ("no_ignore", config.no_ignore.into()),
+        ("symlinks", match config.symlink_policy {
+            SymlinkPolicy::Never => "NEVER",
+            SymlinkPolicy::Follow => "FOLLOW",
+            SymlinkPolicy::WithinRoot => "WITHIN-ROOT",
+        }.into()),
+        ("read_fifo", config.read_fifo.into()),
+        ("strict", config.strict.into()),
+        ("jobs", config.jobs.unwrap_or_else(parallel::default_jobs).into()),
+        ("binary", config.count_binary_files.into()),
+        ("fallback_encoding", config.fallback_encoding.map(|e| e.to_string()).into()),
+        ("lossy", config.lossy.into()),
+        ("line_endings", match config.line_terminators {
+            LineTerminators::Lf => "LF",
+            LineTerminators::Any => "ANY",
+            LineTerminators::Unicode => "UNICODE",
+        }.into()),
+        ("count_final_line", config.count_final_line.into()),
+        ("classify", config.classify_lines.into()),
+        ("group_by_language", config.group_by_language.into()),
+        ("measures", Json::object([
+            ("bytes", measures.bytes.into()),
+            ("chars", measures.chars.into()),
+            ("graphemes", measures.graphemes.into()),
+            ("words", measures.words.into()),
+            ("max_line_length", measures.max_line_length.into()),
+        ])),
+        ("audit", config.audit.into()),
+        ("deny", config.denied_checks.iter().map(|check| check.to_string()).collect::<Vec<_>>().into()),
+        ("line_stats", config.line_stats.into()),
+        ("long_lines", config.long_line_limit.into()),
+    ]);
+}
+
+/**
+ * everything counted about one file
+ */
+pub fn file_record(path: &str, count: &FileCount) -> Json {
+    let measurements = &count.measurements;
+    let mut record = vec![
+        ("path", path.into()),
+        ("language", count.language.map(|l| l.name).into()),
+        ("encoding", count.encoding.to_string().into()),
+        ("size", count.size.into()),
+        ("lines", count.lines.into()),
+    ];
+    if let Some(stats) = count.line_stats {
+        record.push(("line_stats", line_stats_record(&stats)));
+    }
+    //the wc style counts, only if they were asked for
+    for (name, value) in [
+        ("words", measurements.words),
+        ("chars", measurements.chars),
+        ("graphemes", measurements.graphemes),
+        ("bytes", measurements.bytes),
+        ("max_line_bytes", measurements.max_line_bytes),
+        ("max_line_columns", measurements.max_line_width),
+    ] {
+        if let Some(value) = value {
+            record.push((name, value.into()));
+        }
+    }
+    if let Some(hygiene) = &count.hygiene {
+        record.push(("audit", Json::object([
+            ("lf", hygiene.lf.into()),
+            ("crlf", hygiene.crlf.into()),
+            ("cr", hygiene.cr.into()),
+            ("trailing_whitespace_lines", hygiene.trailing_whitespace.clone().into()),
+            ("mixed_indentation_lines", hygiene.mixed_indentation.clone().into()),
+            ("tab_indented_lines", hygiene.tab_indented.into()),
+            ("space_indented_lines", hygiene.space_indented.into()),
+            ("ends_with_newline", hygiene.ends_with_newline.into()),
+        ])));
+    }
+    if let Some(line_lengths) = &count.line_lengths {
+        record.push(("line_lengths", Json::object([
+            ("distribution", distribution_record(&line_lengths.distribution)),
+            ("long_lines", Json::Array(line_lengths.long_lines.iter().map(|long_line| Json::object([
+                ("line", long_line.line_number.into()),
+                ("columns", long_line.columns.into()),
+            ])).collect())),
+        ])));
+    }
+    record.push(("error", Json::Null));
+    return Json::Object(record.into_iter().map(|(key, value)| (key.to_string(), value)).collect());
+}
+
+/**
+ * a file that couldn't be counted, with the same fields as any other, so consumers don't have to check which kind it is
+ */
+pub fn failed_file_record(path: &str, message: &str) -> Json {
+    return Json::object([
+        ("path", path.into()),
+        ("language", Json::Null),
+        ("encoding", Json::Null),
+        ("size", Json::Null),
+        ("lines", Json::Null),
+        ("error", message.into()),
+    ]);
+}
+
+/**
+ * a file that wasn't counted on purpose, like a binary file, reason says why
+ */
+pub fn skipped_file_record(path: &str, reason: &str) -> Json {
+    return Json::object([
+        ("path", path.into()),
+        ("language", Json::Null),
+        ("encoding", Json::Null),
+        ("size", Json::Null),
+        ("lines", Json::Null),
+        ("skipped", reason.into()),
+        ("error", Json::Null),
+    ]);
+}
+
+/**
+ * everything counted across every file
+ */
+pub fn totals_record(totals: &Totals) -> Json {
+    let mut record = vec![
+        ("files", totals.files.into()),
+        ("lines", totals.lines.into()),
+        ("size", totals.size.into()),
+    ];
+    if let Some(stats) = totals.line_stats {
+        record.push(("line_stats", line_stats_record(&stats)));
+    }
+    if !totals.lengths.is_empty() {
+        record.push(("line_lengths", distribution_record(&totals.lengths)));
+        record.push(("long_lines", totals.long_lines.into()));
+    }
+    record.push(("languages", Json::Object(totals.languages.iter().map(|(name, (files, lines))| {
+        (name.to_string(), Json::object([("files", (*files).into()), ("lines", (*lines).into())]))
+    }).collect())));
+    record.extend([
+        ("failed_files", totals.failed_files.into()),
+        ("skipped_binary_files", totals.skipped_binary_files.into()),
+        ("skipped_special_files", totals.skipped_special_files.into()),
+        ("traversal_errors", totals.traversal_errors.into()),
+        ("failed_audits", totals.failed_audits.into()),
+        ("elapsed_seconds", totals.elapsed.as_secs_f64().into()),
+    ]);
+    return Json::Object(record.into_iter().map(|(key, value)| (key.to_string(), value)).collect());
+}
+
+fn line_stats_record(stats: &LineStats) -> Json {
+    return Json::object([("code", stats.code.into()), ("comments", stats.comment.into()), ("blank", stats.blank.into())]);
+}
+
+fn distribution_record(distribution: &crate::lengths::LengthDistribution) -> Json {
+    return Json::object([
+        ("min", distribution.min().into()),
+        ("max", distribution.max().into()),
+        ("mean", distribution.mean().into()),
+        ("p50", distribution.percentile(50.0).into()),
+        ("p90", distribution.percentile(90.0).into()),
+        ("p99", distribution.percentile(99.0).into()),
+    ]);
+}
+
+/**
+ * --format=JSON, one document with the schema version, the options used, every file, and the totals
+ * the document can only be written once everything is counted
+ * files is left out with config.summary_only, and totals with config.no_summary
+ */
+pub struct JsonReport<'a> {
+    config: &'a Config,
+    files: Vec<Json>,
+    warnings: Vec<Json>,
+}
+impl JsonReport<'_> {
+    pub fn new(config: &Config) -> JsonReport<'_> {
+        return JsonReport { config, files: Vec::new(), warnings: Vec::new() };
+    }
+}
+impl Report for JsonReport<'_> {
+    fn file(&mut self, path: &str, count: &FileCount) {
+        self.files.push(file_record(path, count));
+    }
+
+    fn failed(&mut self, path: &str, message: &str) {
+        self.files.push(failed_file_record(path, message));
+    }
+
+    fn binary(&mut self, path: &str) {
+        self.files.push(skipped_file_record(path, "binary"));
+    }
+
+    fn warning(&mut self, path: &Path, message: &str) {
+        self.warnings.push(Json::object([("path", path.to_string_lossy().as_ref().into()), ("message", message.into())]));
+    }
+
+    fn finish(&mut self, totals: &Totals) {
+        let mut document = vec![
+            ("schema_version".to_string(), Json::Integer(SCHEMA_VERSION)),
+            ("config".to_string(), config_record(self.config)),
+        ];
+        if !self.config.summary_only {
+            document.push(("files".to_string(), Json::Array(std::mem::take(&mut self.files))));
+        }
+        document.push(("warnings".to_string(), Json::Array(std::mem::take(&mut self.warnings))));
+        if !self.config.no_summary {
+            document.push(("totals".to_string(), totals_record(totals)));
+        }
+        println!("{}", Json::Object(document).pretty());
+    }
+}
+
+/**
+ * --format=NDJSON, one compact record per line, written as soon as each file is counted, ending with a summary record
+ * every record starts with a type field, which is file, warning, or summary, and the schema version,
+ * so a stream can be read without its summary
+ * there are no file records with config.summary_only, and no summary with config.no_summary
+ */
+pub struct NdjsonReport<'a> {
+    config: &'a Config,
+}
+impl NdjsonReport<'_> {
+    pub fn new(config: &Config) -> NdjsonReport<'_> {
+        return NdjsonReport { config };
+    }
+}
+impl Report for NdjsonReport<'_> {
+    fn file(&mut self, path: &str, count: &FileCount) {
+        println!("{}", typed("file", file_record(path, count)));
+    }
+
+    fn failed(&mut self, path: &str, message: &str) {
+        println!("{}", typed("file", failed_file_record(path, message)));
+    }
+
+    fn binary(&mut self, path: &str) {
+        println!("{}", typed("file", skipped_file_record(path, "binary")));
+    }
+
+    fn warning(&mut self, path: &Path, message: &str) {
+        println!("{}", typed("warning", Json::object([("path", path.to_string_lossy().as_ref().into()), ("message", message.into())])));
+    }
+
+    fn finish(&mut self, totals: &Totals) {
+        if self.config.no_summary {
+            return;
+        }
+        println!("{}", typed("summary", Json::object([
+            ("config", config_record(self.config)),
+            ("totals", totals_record(totals)),
+        ])));
+    }
+}
+
+//puts a type field and the schema version at the start of an object, so the records of a stream can be told apart
+fn typed(kind: &str, record: Json) -> Json {
+    match record {
+        Json::Object(mut fields) => {
+            fields.splice(0..0, [("type".to_string(), kind.into()), ("schema_version".to_string(), Json::Integer(SCHEMA_VERSION))]);
+            return Json::Object(fields);
+        },
+        other => return other,
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn write_values() {
+        let value = Json::object([
+            ("name", "a \"quoted\"\\path\n\u{1}".into()),
+            ("count", 3usize.into()),
+            ("mean", 2.5.into()),
+            ("nothing", Option::<usize>::None.into()),
+            ("list", vec![1usize, 2].into()),
+            ("empty", Json::Array(Vec::new())),
+        ]);
+        assert_eq!(value.to_string(), r#"{"name":"a \"quoted\"\\path\n\u0001","count":3,"mean":2.5,"nothing":null,"list":[1,2],"empty":[]}"#);
+        assert_eq!(Json::Float(f64::NAN).to_string(), "null");
+    }
+    #[test]
+    fn pretty_print() {
+        let value = Json::object([("a", vec![1usize].into()), ("b", Json::object([]))]);
+        assert_eq!(value.pretty(), "{\n  \"a\": [\n    1\n  ],\n  \"b\": {}\n}");
+    }
+    #[test]
+    fn config_has_every_option() {
+        let args: Vec<String> = [
+            "-r", "--symlinks=WITHIN-ROOT", "--read-fifo", "--strict", "-j", "3", "--binary", "--fallback-encoding", "latin-1", "--lossy",
+            "-c", "-g", "-w", "-m", "--deny", "crlf,no-final-newline", "--line-stats", "--long-lines", "100", "../",
+        ].iter().map(|s| s.to_string()).collect();
+        let config = Config::new(&args).unwrap();
+        let record = match config_record(&config) {
+            Json::Object(fields) => fields,
+            other => panic!("config isn't an object: {}", other),
+        };
+        let field = |name: &str| record.iter().find(|(key, _)| key == name).map(|(_, value)| value.to_string()).unwrap_or_else(|| panic!("no {} field", name));
+        assert_eq!(field("recursive"), "true");
+        assert_eq!(field("symlinks"), "\"WITHIN-ROOT\"");
+        assert_eq!(field("read_fifo"), "true");
+        assert_eq!(field("strict"), "true");
+        assert_eq!(field("jobs"), "3");
+        assert_eq!(field("binary"), "true");
+        assert_eq!(field("fallback_encoding"), "\"ISO-8859-1\"");
+        assert_eq!(field("lossy"), "true");
+        assert_eq!(field("classify"), "true");
+        assert_eq!(field("group_by_language"), "true");
+        assert_eq!(field("measures"), r#"{"bytes":false,"chars":true,"graphemes":false,"words":true,"max_line_length":false}"#);
+        assert_eq!(field("audit"), "true");
+        assert_eq!(field("deny"), r#"["CRLF","NO-FINAL-NEWLINE"]"#);
+        assert_eq!(field("line_stats"), "true");
+        assert_eq!(field("long_lines"), "100");
+    }
+    #[test]
+    fn typed_records() {
+        let record = typed("file", failed_file_record("a\nb.rs", "Could not read contents of a\nb.rs"));
+        //one record per line, so newlines in paths have to stay escaped
+        assert_eq!(record.to_string().lines().count(), 1);
+        assert!(record.to_string().starts_with(r#"{"type":"file","schema_version":1,"path":"a\nb.rs","#));
+    }
+}
@@ -0,0 +1,269 @@
+use std::{
+    fs::File,
+    io::{Read, Seek, SeekFrom},
+    path::Path,
+};
+
+/**
+ * describes a language: how to recognize its files, and the comment and string syntax used to classify lines as code, comments, or blanks
+ */
+#[derive(Debug, PartialEq)]
+pub struct Language {
+    pub name: &'static str,
+    pub aliases: &'static [&'static str], //other names the language goes by, used by --filter, shebangs, and modelines
+    pub extensions: &'static [&'static str],
+    pub filenames: &'static [&'static str], //exact file names, these take priority over extensions
+    pub interpreters: &'static [&'static str], //programs that may appear in a shebang line
+    pub line_comments: &'static [&'static str],
+    pub block_comments: &'static [(&'static str, &'static str)],
+    pub nested_block_comments: bool,
+    pub string_delimiters: &'static [&'static str], //longest delimiters should come first, so """ is found before "
+    pub char_literals: bool, //if ' only starts a one character literal, and is otherwise something else, like a Rust lifetime
+}
+
+//comment syntax shared by many languages
+const C_LINE: &[&str] = &["//"];
+const C_BLOCK: &[(&str, &str)] = &[("/*", "*/")];
+const C_STRINGS: &[&str] = &["\"", "'"];
+const HASH_LINE: &[&str] = &["#"];
+const NO_BLOCK: &[(&str, &str)] = &[];
+const NO_LINE: &[&str] = &[];
+const NO_STRINGS: &[&str] = &[];
+const SHELL_STRINGS: &[&str] = &["\"", "'"];
+
+/**
+ * built-in table of known languages
+ */
+pub const LANGUAGES: &[Language] = &[
+    Language { name: "Rust", aliases: &["rust"], extensions: &["rs"], filenames: &[], interpreters: &[], line_comments: C_LINE, block_comments: C_BLOCK, nested_block_comments: true, string_delimiters: &["\""], char_literals: true },
+    Language { name: "C", aliases: &["c"], extensions: &["c", "h"], filenames: &[], interpreters: &[], line_comments: C_LINE, block_comments: C_BLOCK, nested_block_comments: false, string_delimiters: C_STRINGS, char_literals: false },
+    Language { name: "C++", aliases: &["cpp", "cplusplus"], extensions: &["cpp", "cc", "cxx", "hpp", "hh", "hxx"], filenames: &[], interpreters: &[], line_comments: C_LINE, block_comments: C_BLOCK, nested_block_comments: false, string_delimiters: C_STRINGS, char_literals: false },
+    Language { name: "C#", aliases: &["csharp", "cs"], extensions: &["cs"], filenames: &[], interpreters: &[], line_comments: C_LINE, block_comments: C_BLOCK, nested_block_comments: false, string_delimiters: C_STRINGS, char_literals: false },
+    Language { name: "Java", aliases: &["java"], extensions: &["java"], filenames: &[], interpreters: &[], line_comments: C_LINE, block_comments: C_BLOCK, nested_block_comments: false, string_delimiters: C_STRINGS, char_literals: false },
+    Language { name: "JavaScript", aliases: &["javascript", "js"], extensions: &["js", "mjs", "cjs", "jsx"], filenames: &[], interpreters: &["node", "nodejs"], line_comments: C_LINE, block_comments: C_BLOCK, nested_block_comments: false, string_delimiters: &["\"", "'", "`"], char_literals: false },
+    Language { name: "TypeScript", aliases: &["typescript", "ts"], extensions: &["ts", "tsx"], filenames: &[], interpreters: &["ts-node", "deno"], line_comments: C_LINE, block_comments: C_BLOCK, nested_block_comments: false, string_delimiters: &["\"", "'", "`"], char_literals: false },
+    Language { name: "Go", aliases: &["go", "golang"], extensions: &["go"], filenames: &[], interpreters: &[], line_comments: C_LINE, block_comments: C_BLOCK, nested_block_comments: false, string_delimiters: &["\"", "'", "`"], char_literals: false },
+    Language { name: "Swift", aliases: &["swift"], extensions: &["swift"], filenames: &[], interpreters: &[], line_comments: C_LINE, block_comments: C_BLOCK, nested_block_comments: true, string_delimiters: &["\"\"\"", "\""], char_literals: false },
+    Language { name: "Kotlin", aliases: &["kotlin"], extensions: &["kt", "kts"], filenames: &[], interpreters: &[], line_comments: C_LINE, block_comments: C_BLOCK, nested_block_comments: true, string_delimiters: &["\"\"\"", "\"", "'"], char_literals: false },
+    Language { name: "Python", aliases: &["python", "py"], extensions: &["py", "pyw"], filenames: &["SConstruct", "SConscript"], interpreters: &["python", "python2", "python3", "pypy", "pypy3"], line_comments: HASH_LINE, block_comments: NO_BLOCK, nested_block_comments: false, string_delimiters: &["\"\"\"", "'''", "\"", "'"], char_literals: false },
+    Language { name: "Ruby", aliases: &["ruby", "rb"], extensions: &["rb"], filenames: &["Rakefile", "Gemfile"], interpreters: &["ruby"], line_comments: HASH_LINE, block_comments: &[("=begin", "=end")], nested_block_comments: false, string_delimiters: SHELL_STRINGS, char_literals: false },
+    Language { name: "Shell", aliases: &["shell", "sh", "bash", "zsh"], extensions: &["sh", "bash", "zsh"], filenames: &[".bashrc", ".bash_profile", ".profile", ".zshrc"], interpreters: &["sh", "bash", "zsh", "dash", "ksh"], line_comments: HASH_LINE, block_comments: NO_BLOCK, nested_block_comments: false, string_delimiters: SHELL_STRINGS, char_literals: false },
+    Language { name: "Perl", aliases: &["perl"], extensions: &["pl", "pm"], filenames: &[], interpreters: &["perl"], line_comments: HASH_LINE, block_comments: NO_BLOCK, nested_block_comments: false, string_delimiters: SHELL_STRINGS, char_literals: false },
+    Language { name: "Lua", aliases: &["lua"], extensions: &["lua"], filenames: &[], interpreters: &["lua"], line_comments: &["--"], block_comments: &[("--[[", "]]")], nested_block_comments: false, string_delimiters: SHELL_STRINGS, char_literals: false },
+    Language { name: "SQL", aliases: &["sql"], extensions: &["sql"], filenames: &[], interpreters: &[], line_comments: &["--"], block_comments: C_BLOCK, nested_block_comments: false, string_delimiters: &["'"], char_literals: false },
+    Language { name: "Haskell", aliases: &["haskell"], extensions: &["hs"], filenames: &[], interpreters: &["runhaskell"], line_comments: &["--"], block_comments: &[("{-", "-}")], nested_block_comments: true, string_delimiters: &["\""], char_literals: false },
+    Language { name: "HTML", aliases: &["html"], extensions: &["html", "htm"], filenames: &[], interpreters: &[], line_comments: NO_LINE, block_comments: &[("<!--", "-->")], nested_block_comments: false, string_delimiters: NO_STRINGS, char_literals: false },
+    Language { name: "XML", aliases: &["xml"], extensions: &["xml", "svg"], filenames: &[], interpreters: &[], line_comments: NO_LINE, block_comments: &[("<!--", "-->")], nested_block_comments: false, string_delimiters: NO_STRINGS, char_literals: false },
+    Language { name: "CSS", aliases: &["css"], extensions: &["css"], filenames: &[], interpreters: &[], line_comments: NO_LINE, block_comments: C_BLOCK, nested_block_comments: false, string_delimiters: C_STRINGS, char_literals: false },
+    Language { name: "TOML", aliases: &["toml"], extensions: &["toml"], filenames: &["Cargo.lock"], interpreters: &[], line_comments: HASH_LINE, block_comments: NO_BLOCK, nested_block_comments: false, string_delimiters: &["\"\"\"", "'''", "\"", "'"], char_literals: false },
+    Language { name: "YAML", aliases: &["yaml"], extensions: &["yaml", "yml"], filenames: &[], interpreters: &[], line_comments: HASH_LINE, block_comments: NO_BLOCK, nested_block_comments: false, string_delimiters: SHELL_STRINGS, char_literals: false },
+    Language { name: "Markdown", aliases: &["markdown"], extensions: &["md", "markdown"], filenames: &[], interpreters: &[], line_comments: NO_LINE, block_comments: NO_BLOCK, nested_block_comments: false, string_delimiters: NO_STRINGS, char_literals: false },
+    Language { name: "Plain Text", aliases: &["text", "txt"], extensions: &["txt"], filenames: &[], interpreters: &[], line_comments: NO_LINE, block_comments: NO_BLOCK, nested_block_comments: false, string_delimiters: NO_STRINGS, char_literals: false },
+    Language { name: "Makefile", aliases: &["make", "makefile"], extensions: &["mk", "mak"], filenames: &["Makefile", "makefile", "GNUmakefile"], interpreters: &["make"], line_comments: HASH_LINE, block_comments: NO_BLOCK, nested_block_comments: false, string_delimiters: NO_STRINGS, char_literals: false },
+    Language { name: "Dockerfile", aliases: &["dockerfile", "docker"], extensions: &["dockerfile"], filenames: &["Dockerfile", "Containerfile"], interpreters: &[], line_comments: HASH_LINE, block_comments: NO_BLOCK, nested_block_comments: false, string_delimiters: NO_STRINGS, char_literals: false },
+    Language { name: "CMake", aliases: &["cmake"], extensions: &["cmake"], filenames: &["CMakeLists.txt"], interpreters: &[], line_comments: HASH_LINE, block_comments: &[("#[[", "]]")], nested_block_comments: false, string_delimiters: &["\""], char_literals: false },
+];
+
+//how many bytes at the start and end of a file are searched for a shebang or modeline
+const HEAD_SIZE: usize = 1024;
+//how many lines at the start and end of a file are searched for a modeline, like vim does
+const MODELINE_LINES: usize = 5;
+
+/**
+ * find the language with a given name or alias, ignoring case and any characters that aren't letters
+ * so "rust", "Python", "c++" and "C#" all work
+ */
+pub fn from_name(name: &str) -> Option<&'static Language> {
+    let normalize = |s: &str| s.replace('+', "p").replace('#', "sharp").chars().filter(|c| c.is_ascii_alphabetic()).collect::<String>().to_ascii_lowercase();
+    let name = normalize(name);
+    if name.is_empty() {
+        return None;
+    }
+    return LANGUAGES.iter().find(|language| normalize(language.name) == name || language.aliases.iter().any(|alias| normalize(alias) == name));
+}
+
+/**
+ * find the language that uses a given file name exactly, like Makefile or Dockerfile
+ */
+pub fn from_filename(filename: &str) -> Option<&'static Language> {
+    return LANGUAGES.iter().find(|language| language.filenames.contains(&filename));
+}
+
+/**
+ * find the language that uses a given file extension, ignoring case
+ */
+pub fn from_extension(extension: &str) -> Option<&'static Language> {
+    return LANGUAGES.iter().find(|language| language.extensions.iter().any(|ext| ext.eq_ignore_ascii_case(extension)));
+}
+
+/**
+ * find the language named by the shebang line of a file, if it has one
+ * handles both "#!/usr/bin/python3" and "#!/usr/bin/env python3"
+ */
+pub fn from_shebang(first_line: &str) -> Option<&'static Language> {
+    let command = first_line.strip_prefix("#!")?;
+    let mut words = command.split_whitespace();
+    let mut program = words.next()?.rsplit('/').next()?;
+    if program == "env" {
+        //skip over any flags given to env, like -S
+        program = words.find(|word| !word.starts_with('-'))?;
+    }
+    return LANGUAGES.iter().find(|language| language.interpreters.contains(&program))
+        //handle versioned interpreters like python3.11
+        .or_else(|| {
+            let unversioned = program.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+            LANGUAGES.iter().find(|language| language.interpreters.contains(&unversioned))
+        });
+}
+
+/**
+ * find the language named by a vim or emacs modeline in a line, if it has one
+ * handles "vim: set ft=python:", "vi: filetype=sh", and "-*- mode: ruby -*-"
+ */
+pub fn from_modeline(line: &str) -> Option<&'static Language> {
+    //emacs
+    if let Some(start) = line.find("-*-") {
+        let rest = &line[start + 3..];
+        let variables = &rest[..rest.find("-*-")?];
+        if !variables.contains(':') {
+            //short form, "-*- python -*-"
+            return from_name(variables.trim());
+        }
+        return variables.split(';')
+            .filter_map(|variable| variable.split_once(':'))
+            .find(|(key, _)| key.trim().eq_ignore_ascii_case("mode"))
+            .and_then(|(_, value)| from_name(value.trim()));
+    }
+    //vim
+    for marker in ["vim:", "vi:", "ex:"] {
+        if let Some(start) = line.find(marker) {
+            return line[start + marker.len()..]
+                .split(|c: char| c == ':' || c.is_whitespace())
+                .filter_map(|option| option.split_once('='))
+                .find(|(key, _)| *key == "ft" || *key == "filetype")
+                .and_then(|(_, value)| from_name(value));
+        }
+    }
+    return None;
+}
+
+/**
+ * find the language of a file based only on its path, checking the exact file name before the extension
+ */
+pub fn from_path(path: &Path) -> Option<&'static Language> {
+    return path.file_name().and_then(|name| name.to_str()).and_then(from_filename)
+        .or_else(|| path.extension().and_then(|ext| ext.to_str()).and_then(from_extension));
+}
+
+/**
+ * find the language of a file based on its path, and if that doesn't work, a shebang,
+ * or a modeline in the first or last few lines of file_contents
+ */
+pub fn detect(path: &Path, file_contents: &str) -> Option<&'static Language> {
+    return detect_in(path, file_contents, file_contents);
+}
+
+/**
+ * like detect, with the start and the end of a file that wasn't read all at once apart, since only they are searched
+ * if tail is empty, head is searched for a modeline at the end instead
+ */
+pub fn detect_in(path: &Path, head: &str, tail: &str) -> Option<&'static Language> {
+    let tail = if tail.is_empty() { head } else { tail };
+    if let Some(language) = from_path(path) {
+        return Some(language);
+    }
+    let mut lines = head.lines().take(MODELINE_LINES);
+    return lines.next().and_then(|first_line| from_shebang(first_line).or_else(|| from_modeline(first_line)))
+        .or_else(|| lines.find_map(from_modeline))
+        .or_else(|| tail.lines().rev().take(MODELINE_LINES).find_map(from_modeline));
+}
+
+/**
+ * reads the end of a file that's already open, where a modeline at the end would be
+ * only regular files are read, since a pipe or device can't be read twice, so anything else has no end to read
+ */
+pub fn read_end(file: &mut File) -> Vec<u8> {
+    let mut tail = Vec::with_capacity(HEAD_SIZE);
+    let length = match file.metadata() {
+        Ok(metadata) if metadata.is_file() => metadata.len(),
+        _ => return tail,
+    };
+    if file.seek(SeekFrom::End(-(length.min(HEAD_SIZE as u64) as i64))).is_ok() {
+        let _ = file.read_to_end(&mut tail);
+    }
+    return tail;
+}
+
+/**
+ * like detect, but reads the start and end of the file itself if the path isn't enough
+ */
+pub fn detect_file(path: &Path) -> Option<&'static Language> {
+    if let Some(language) = from_path(path) {
+        return Some(language);
+    }
+    let mut file = File::open(path).ok()?;
+    let mut head = Vec::with_capacity(HEAD_SIZE);
+    file.by_ref().take(HEAD_SIZE as u64).read_to_end(&mut head).ok()?;
+    let tail = if head.len() == HEAD_SIZE { read_end(&mut file) } else { Vec::new() };
+    return detect_in(path, &String::from_utf8_lossy(&head), &String::from_utf8_lossy(&tail));
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detect_by_filename_before_extension() {
+        assert_eq!(from_path(Path::new("project/CMakeLists.txt")).map(|l| l.name), Some("CMake"));
+        assert_eq!(from_path(Path::new("notes.txt")).map(|l| l.name), Some("Plain Text"));
+        assert_eq!(from_path(Path::new("Makefile")).map(|l| l.name), Some("Makefile"));
+        assert_eq!(from_path(Path::new("Dockerfile")).map(|l| l.name), Some("Dockerfile"));
+        assert_eq!(from_path(Path::new("LICENSE")), None);
+    }
+    #[test]
+    fn detect_by_name() {
+        assert_eq!(from_name("rust").map(|l| l.name), Some("Rust"));
+        assert_eq!(from_name("PYTHON").map(|l| l.name), Some("Python"));
+        assert_eq!(from_name("c++").map(|l| l.name), Some("C++"));
+        assert_eq!(from_name("cobol"), None);
+    }
+    #[test]
+    fn detect_by_shebang() {
+        assert_eq!(from_shebang("#!/usr/bin/env python3").map(|l| l.name), Some("Python"));
+        assert_eq!(from_shebang("#!/usr/bin/python3.11").map(|l| l.name), Some("Python"));
+        assert_eq!(from_shebang("#!/bin/bash -e").map(|l| l.name), Some("Shell"));
+        assert_eq!(from_shebang("#!/usr/bin/env -S node --harmony").map(|l| l.name), Some("JavaScript"));
+        assert_eq!(from_shebang("# just a comment"), None);
+    }
+    #[test]
+    fn detect_by_modeline() {
+        assert_eq!(from_modeline("# vim: set ft=python:").map(|l| l.name), Some("Python"));
+        assert_eq!(from_modeline("// vim: filetype=rust").map(|l| l.name), Some("Rust"));
+        assert_eq!(from_modeline("# -*- mode: ruby; coding: utf-8 -*-").map(|l| l.name), Some("Ruby"));
+        assert_eq!(from_modeline(";; -*- sh -*-").map(|l| l.name), Some("Shell"));
+        assert_eq!(from_modeline("nothing to see here"), None);
+    }
+    #[test]
+    fn detect_from_contents() {
+        assert_eq!(detect(Path::new("script"), "#!/bin/sh\necho hi\n").map(|l| l.name), Some("Shell"));
+        assert_eq!(detect(Path::new("script"), "\n\n# vim: ft=perl\n").map(|l| l.name), Some("Perl"));
+        assert_eq!(detect(Path::new("main.rs"), "#!/bin/sh\n").map(|l| l.name), Some("Rust"));
+        assert_eq!(detect(Path::new("LICENSE"), "MIT License\n"), None);
+    }
+    #[test]
+    fn detect_modeline_at_end() {
+        let text = format!("{}# vim: set ft=python:\n\n", "x = 1\n".repeat(20));
+        assert_eq!(detect(Path::new("script"), &text).map(|l| l.name), Some("Python"));
+        //too far from either end
+        let text = format!("{}# vim: set ft=python:\n{}", "x = 1\n".repeat(20), "x = 1\n".repeat(20));
+        assert_eq!(detect(Path::new("script"), &text), None);
+
+        //reading the file, the end is read separately from the start
+        let path = std::env::temp_dir().join(format!("line-counter-modeline-{}", std::process::id()));
+        std::fs::write(&path, format!("{}// vim: ft=rust\n", "let x = 1;\n".repeat(HEAD_SIZE))).unwrap();
+        assert_eq!(detect_file(&path).map(|l| l.name), Some("Rust"));
+        std::fs::remove_file(&path).unwrap();
+    }
+}
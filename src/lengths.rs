@@ -0,0 +1,161 @@
+use std::{collections::BTreeMap, fmt};
+
+use crate::measure::display_width;
+
+/**
+ * how many lines there are of each length, in display columns
+ * kept as counts rather than every length, so distributions of whole trees stay small and can be merged
+ */
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct LengthDistribution {
+    counts: BTreeMap<usize, usize>, //length -> lines that long
+    lines: usize,
+    total_length: usize,
+}
+impl LengthDistribution {
+    /**
+     * adds a line of length columns
+     */
+    pub fn add(&mut self, length: usize) {
+        *self.counts.entry(length).or_default() += 1;
+        self.lines += 1;
+        self.total_length += length;
+    }
+
+    /**
+     * adds every line of other
+     */
+    pub fn merge(&mut self, other: &LengthDistribution) {
+        for (length, count) in &other.counts {
+            *self.counts.entry(*length).or_default() += count;
+        }
+        self.lines += other.lines;
+        self.total_length += other.total_length;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.lines == 0;
+    }
+
+    pub fn min(&self) -> Option<usize> {
+        return self.counts.keys().next().copied();
+    }
+
+    pub fn max(&self) -> Option<usize> {
+        return self.counts.keys().next_back().copied();
+    }
+
+    pub fn mean(&self) -> Option<f64> {
+        if self.is_empty() {
+            return None;
+        }
+        return Some(self.total_length as f64 / self.lines as f64);
+    }
+
+    /**
+     * the length that percent of lines are no longer than, using the nearest rank
+     */
+    pub fn percentile(&self, percent: f64) -> Option<usize> {
+        if self.is_empty() {
+            return None;
+        }
+        let rank = ((percent / 100.0 * self.lines as f64).ceil() as usize).clamp(1, self.lines);
+        let mut seen = 0;
+        for (length, count) in &self.counts {
+            seen += count;
+            if seen >= rank {
+                return Some(*length);
+            }
+        }
+        return self.max();
+    }
+}
+impl fmt::Display for LengthDistribution {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (self.min(), self.max(), self.mean()) {
+            (Some(min), Some(max), Some(mean)) => write!(f, "min {}, max {}, mean {:.1}, p50 {}, p90 {}, p99 {}",
+                min, max, mean, self.percentile(50.0).unwrap_or(0), self.percentile(90.0).unwrap_or(0), self.percentile(99.0).unwrap_or(0)),
+            _ => write!(f, "no lines"),
+        }
+    }
+}
+
+/**
+ * a line longer than the column limit
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LongLine {
+    pub line_number: usize, //starting at 1
+    pub columns: usize,
+}
+
+/**
+ * the lengths of every line in some text
+ */
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct LineLengths {
+    pub distribution: LengthDistribution,
+    pub long_lines: Vec<LongLine>, //only if a column limit was given
+}
+
+/**
+ * measures every line of text in display columns, not counting line endings,
+ * and finds the lines longer than column_limit, if there is one
+ */
+pub fn line_lengths(text: &str, column_limit: Option<usize>) -> LineLengths {
+    let mut lengths = LineLengths::default();
+    for (i, line) in text.split_inclusive('\n').enumerate() {
+        let line = line.strip_suffix('\n').unwrap_or(line);
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        let columns = display_width(line);
+        lengths.distribution.add(columns);
+        if column_limit.is_some_and(|limit| columns > limit) {
+            lengths.long_lines.push(LongLine { line_number: i + 1, columns });
+        }
+    }
+    return lengths;
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn distribution_stats() {
+        let mut distribution = LengthDistribution::default();
+        assert_eq!(distribution.min(), None);
+        assert_eq!(distribution.percentile(50.0), None);
+        assert_eq!(distribution.to_string(), "no lines");
+
+        for length in 1..=100 {
+            distribution.add(length);
+        }
+        assert_eq!(distribution.min(), Some(1));
+        assert_eq!(distribution.max(), Some(100));
+        assert_eq!(distribution.mean(), Some(50.5));
+        assert_eq!(distribution.percentile(50.0), Some(50));
+        assert_eq!(distribution.percentile(90.0), Some(90));
+        assert_eq!(distribution.percentile(99.0), Some(99));
+        assert_eq!(distribution.percentile(0.0), Some(1));
+        assert_eq!(distribution.percentile(100.0), Some(100));
+        assert_eq!(distribution.to_string(), "min 1, max 100, mean 50.5, p50 50, p90 90, p99 99");
+    }
+    #[test]
+    fn merge_distributions() {
+        let mut a = line_lengths("aa\nbbbb\n", None).distribution;
+        let b = line_lengths("\ncccccc", None).distribution;
+        a.merge(&b);
+        assert_eq!(a, line_lengths("aa\nbbbb\n\ncccccc", None).distribution);
+        assert_eq!((a.min(), a.max(), a.mean()), (Some(0), Some(6), Some(3.0)));
+    }
+    #[test]
+    fn find_long_lines() {
+        let lengths = line_lengths("short\r\nthis one is long\n\tindented\nok\n", Some(8));
+        assert_eq!(lengths.long_lines, vec![LongLine { line_number: 2, columns: 16 }, LongLine { line_number: 3, columns: 16 }]);
+        assert_eq!(lengths.distribution.max(), Some(16));
+        //no limit, no long lines
+        assert!(line_lengths("this one is long\n", None).long_lines.is_empty());
+    }
+}
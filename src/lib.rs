@@ -1,46 +1,120 @@
 use std::{
-    fs, //access to files / file system
+    fs::{self, File}, //access to files / file system
+    io::Read,
     fmt::Debug,
     error::Error,//allows for some better errors
     path::{Path, PathBuf}, ffi::OsString, //system specific file separator, and path operations
+    time::Instant,
 };
 
+pub mod language;
+mod audit;
+use audit::Hygiene;
+pub use audit::Check;
+mod binary;
+mod classify;
+mod measure;
+mod lengths;
+pub use lengths::{LineLengths, LongLine};
+pub use measure::{Measures, Measurements};
+mod count;
+use count::LineCounter;
+pub mod encoding;
+use encoding::{DecodeOptions, Encoding};
+mod glob;
+use glob::PathPattern;
+mod ignore;
+mod parallel;
+mod report;
+use report::Totals;
+mod json;
+mod delimited;
+mod markdown;
+mod html;
+mod treemap;
+mod walk;
+use walk::{WalkOptions, WalkEntry, SpecialKind};
+pub use walk::SymlinkPolicy;
+pub use classify::{classify_lines, LineStats};
+pub use count::{count_lines_in_text, LineOptions, LineTerminators};
+
 //handles output format
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Default, PartialEq)]
 pub enum FORMAT {
+    #[default]
     Default,
     Bullet,
     Markdown,
     Numeric,
+    Json,
+    Ndjson,
+    Csv,
+    Tsv,
+    Html,
+    SvgTreemap,
 }
 
 //handles parsing of arguments
-const VALID_OPTIONS: [&str; 10] = [
+const VALID_OPTIONS: [&str; 54] = [
     "-f", "--filter",
-    "--format=DEFAULT","--format=BULLET", "--format=MARKDOWN","--format=NUMERIC",
+    "--name", "--require-extension",
+    "--format=DEFAULT","--format=BULLET", "--format=MARKDOWN","--format=NUMERIC", "--format=JSON", "--format=NDJSON", "--format=CSV", "--format=TSV", "--format=HTML", "--format=SVG-TREEMAP",
     "-r", "--recursive",
+    "--no-ignore",
+    "--exclude", "--include",
+    "--strict",
+    "--symlinks=NEVER", "--symlinks=FOLLOW", "--symlinks=WITHIN-ROOT",
+    "--read-fifo",
+    "--binary",
+    "--fallback-encoding", "--lossy", "--show-encoding",
+    "--line-endings=LF", "--line-endings=ANY", "--line-endings=UNICODE", "--count-final-line",
+    "--audit", "--deny",
+    "--bytes", "-m", "--chars", "--graphemes", "-w", "--words", "-L", "--max-line-length",
+    "--line-stats", "--long-lines",
+    "-c", "--classify",
+    "-g", "--group-by-language",
+    "-j", "--jobs",
+    "--no-summary", "--summary-only",
     "-h", "--help",
 ];
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Default, PartialEq)]
 pub struct Config {
     pub path: String,
     pub path_is_directory: bool,
     pub extensions_to_filter_for: Vec<String>,
+    pub names_to_filter_for: Vec<String>,
+    pub require_extension: bool,
     pub output_format: FORMAT,
     pub search_subdirectories_recursively: bool,
+    pub no_ignore: bool,
+    pub exclude_patterns: Vec<String>,
+    pub include_patterns: Vec<String>,
+    pub strict: bool,
+    pub symlink_policy: SymlinkPolicy,
+    pub read_fifo: bool,
+    pub count_binary_files: bool,
+    pub fallback_encoding: Option<Encoding>,
+    pub lossy: bool,
+    pub show_encoding: bool,
+    pub line_terminators: LineTerminators,
+    pub count_final_line: bool,
+    pub audit: bool,
+    pub denied_checks: Vec<Check>, //fail if any file fails one of these
+    pub measures: Measures, //what to count besides lines
+    pub line_stats: bool,
+    pub long_line_limit: Option<usize>, //list every line longer than this many columns
+    pub classify_lines: bool,
+    pub group_by_language: bool,
+    pub jobs: Option<usize>, //how many threads search directories and count files at once, None means one for every CPU
+    pub no_summary: bool, //don't print the total files, lines, skipped and failed files, and time taken
+    pub summary_only: bool, //only print the totals, not every file
     pub show_help: bool,
 }
 impl Config {
+    #[allow(clippy::needless_late_init)] //every variable is declared up front, then set once the arguments are checked
     pub fn new(args: &[String]) -> Result<Config, Box<dyn Error>> {
         //DATA
-        let mut config: Config = Config {
-            path: String::new(),
-            path_is_directory:false,
-            extensions_to_filter_for: Vec::new(),
-            output_format: FORMAT::Default,
-            search_subdirectories_recursively:false,
-            show_help:false,
-        };
+        let mut config: Config = Config::default();
         let options: Vec<String>;
         let path: String;
         let full_path: &Path;
@@ -52,7 +126,7 @@ impl Config {
         }
 
         //parse arguments for options
-        options = (&args[0..]).iter()//iterator of arguments that ignores the first one
+        options = args[0..].iter()//iterator of arguments that ignores the first one
         .filter(|a| a.starts_with("-"))//filter out things that don't start with '-'
         .map(|a|a.to_string())//clone the strings
         .collect(); //collect into vector
@@ -68,19 +142,82 @@ impl Config {
 
         //modify config as needed depending on options passed
         let mut extensions_to_filter_for_or_error = Ok(Vec::new());
+        let mut names_to_filter_for_or_error = Ok(Vec::new());
+        let mut exclude_patterns_or_error = Ok(Vec::new());
+        let mut include_patterns_or_error = Ok(Vec::new());
+        let mut fallback_encoding_or_error = Ok(Vec::new());
+        let mut jobs_or_error = Ok(Vec::new());
+        let mut denied_checks_or_error = Ok(Vec::new());
+        let mut long_line_limit_or_error = Ok(Vec::new());
         options.iter().for_each(|option| {
             match option.as_str() {
                 /* filter for extensions */
                 //if list_after_option() failed, print the error to std_err and set config.extensions_to_filter_for to an empty vector, otherwise set config.extensions_to_filter_for to the vector returned
-                "-f" => extensions_to_filter_for_or_error = get_list_from_args_after_option(&args, "-f"),
-                "--filter" => extensions_to_filter_for_or_error = get_list_from_args_after_option(&args, "--filter"),
+                "-f" => extensions_to_filter_for_or_error = get_list_from_args_after_option(args, "-f"),
+                "--filter" => extensions_to_filter_for_or_error = get_list_from_args_after_option(args, "--filter"),
+                /* filter for file names or globs */
+                "--name" => names_to_filter_for_or_error = get_values_from_args_after_option(args, "--name"),
+                /* skip files without an extension, like older versions did */
+                "--require-extension" => config.require_extension = true,
                 /* output format */
                 "--format=DEFAULT" => config.output_format = FORMAT::Default,
                 "--format=BULLET" => config.output_format = FORMAT::Bullet,
                 "--format=MARKDOWN" => config.output_format = FORMAT::Markdown,
                 "--format=NUMERIC" => config.output_format = FORMAT::Numeric,
+                "--format=JSON" => config.output_format = FORMAT::Json,
+                "--format=NDJSON" => config.output_format = FORMAT::Ndjson,
+                "--format=CSV" => config.output_format = FORMAT::Csv,
+                "--format=TSV" => config.output_format = FORMAT::Tsv,
+                "--format=HTML" => config.output_format = FORMAT::Html,
+                "--format=SVG-TREEMAP" => config.output_format = FORMAT::SvgTreemap,
                 /* search subdirectories recursively */
                 "-r"|"--recursive" => config.search_subdirectories_recursively = true,
+                /* don't skip files matched by .gitignore and friends */
+                "--no-ignore" => config.no_ignore = true,
+                /* globs of paths to skip, or to only count */
+                "--exclude" => exclude_patterns_or_error = get_values_from_args_after_option(args, "--exclude"),
+                "--include" => include_patterns_or_error = get_values_from_args_after_option(args, "--include"),
+                /* fail if anything goes wrong while searching */
+                "--strict" => config.strict = true,
+                /* symlinks */
+                "--symlinks=NEVER" => config.symlink_policy = SymlinkPolicy::Never,
+                "--symlinks=FOLLOW" => config.symlink_policy = SymlinkPolicy::Follow,
+                "--symlinks=WITHIN-ROOT" => config.symlink_policy = SymlinkPolicy::WithinRoot,
+                /* read the path even if it's a named pipe */
+                "--read-fifo" => config.read_fifo = true,
+                /* count files that look binary instead of skipping them */
+                "--binary" => config.count_binary_files = true,
+                /* decoding files that aren't UTF-8 */
+                "--fallback-encoding" => fallback_encoding_or_error = get_values_from_args_after_option(args, "--fallback-encoding"),
+                "--lossy" => config.lossy = true,
+                "--show-encoding" => config.show_encoding = true,
+                /* what ends a line */
+                "--line-endings=LF" => config.line_terminators = LineTerminators::Lf,
+                "--line-endings=ANY" => config.line_terminators = LineTerminators::Any,
+                "--line-endings=UNICODE" => config.line_terminators = LineTerminators::Unicode,
+                "--count-final-line" => config.count_final_line = true,
+                /* report line endings and whitespace problems, and fail on some of them */
+                "--audit" => config.audit = true,
+                "--deny" => denied_checks_or_error = get_values_from_args_after_option(args, "--deny"),
+                /* count more than lines, like wc */
+                "--bytes" => config.measures.bytes = true,
+                "-m"|"--chars" => config.measures.chars = true,
+                "--graphemes" => config.measures.graphemes = true,
+                "-w"|"--words" => config.measures.words = true,
+                "-L"|"--max-line-length" => config.measures.max_line_length = true,
+                /* how long lines are */
+                "--line-stats" => config.line_stats = true,
+                "--long-lines" => long_line_limit_or_error = get_values_from_args_after_option(args, "--long-lines"),
+                /* classify lines as code, comments, and blanks */
+                "-c"|"--classify" => config.classify_lines = true,
+                /* print totals for each language */
+                "-g"|"--group-by-language" => config.group_by_language = true,
+                /* how many threads to search and count with */
+                "-j" => jobs_or_error = get_values_from_args_after_option(args, "-j"),
+                "--jobs" => jobs_or_error = get_values_from_args_after_option(args, "--jobs"),
+                /* whether to print the summary, and only the summary */
+                "--no-summary" => config.no_summary = true,
+                "--summary-only" => config.summary_only = true,
                 /* help */
                 "-h"|"--help" => config.show_help = true,
                 _ => {},
@@ -90,6 +227,61 @@ impl Config {
             Err(e) => return Err( format!("Error finding extensions list: {}", e).into()),
             Ok(vec) => if !vec.is_empty() {config.extensions_to_filter_for = vec;},
         }
+        match names_to_filter_for_or_error {
+            Err(e) => return Err( format!("Error finding file names: {}", e).into()),
+            Ok(vec) => config.names_to_filter_for = vec,
+        }
+        match exclude_patterns_or_error {
+            Err(e) => return Err( format!("Error finding exclude patterns: {}", e).into()),
+            Ok(vec) => config.exclude_patterns = vec,
+        }
+        match include_patterns_or_error {
+            Err(e) => return Err( format!("Error finding include patterns: {}", e).into()),
+            Ok(vec) => config.include_patterns = vec,
+        }
+        match fallback_encoding_or_error {
+            Err(e) => return Err( format!("Error finding fallback encoding: {}", e).into()),
+            Ok(vec) => if let Some(name) = vec.last() {
+                config.fallback_encoding = Some(Encoding::from_name(name).ok_or(format!("Unknown encoding: {}", name))?);
+            },
+        }
+        match denied_checks_or_error {
+            Err(e) => return Err( format!("Error finding checks to deny: {}", e).into()),
+            Ok(vec) => for name in vec.iter().flat_map(|list| list.split(',')).filter(|name| !name.is_empty()) {
+                config.denied_checks.push(Check::from_name(name).ok_or(format!("Unknown check: {}", name))?);
+                config.audit = true; //can't fail an audit without doing one
+            },
+        }
+        match long_line_limit_or_error {
+            Err(e) => return Err( format!("Error finding column limit: {}", e).into()),
+            Ok(vec) => if let Some(limit) = vec.last() {
+                config.long_line_limit = Some(limit.parse::<usize>().map_err(|_e| format!("Column limit must be a whole number, not {}", limit))?);
+            },
+        }
+        match jobs_or_error {
+            Err(e) => return Err( format!("Error finding number of jobs: {}", e).into()),
+            Ok(vec) => if let Some(jobs) = vec.last() {
+                match jobs.parse::<usize>() {
+                    Ok(n) if n > 0 => config.jobs = Some(n),
+                    _ => return Err(format!("Number of jobs must be a positive whole number, not {}", jobs).into()),
+                }
+            },
+        }
+
+        if config.no_summary && config.summary_only {
+            return Err("--no-summary and --summary-only can't be used together".into());
+        }
+        //the line length distribution is only written as text and JSON,
+        //the long lines also go in the markdown list and the long_lines column of CSV and TSV, but nowhere in HTML or the treemap
+        match config.output_format {
+            FORMAT::Markdown | FORMAT::Csv | FORMAT::Tsv | FORMAT::Html | FORMAT::SvgTreemap if config.line_stats => {
+                return Err("--line-stats only works with the DEFAULT, BULLET, NUMERIC, JSON, and NDJSON formats".into());
+            },
+            FORMAT::Html | FORMAT::SvgTreemap if config.long_line_limit.is_some() => {
+                return Err("--long-lines doesn't work with the HTML and SVG-TREEMAP formats".into());
+            },
+            _ => {},
+        }
 
         //if help, exit early
         if config.show_help {
@@ -114,16 +306,13 @@ impl Config {
     }
 }
 //private function that goes through the arguments to find a list after a specified option
+#[allow(clippy::needless_late_init)]
 fn get_list_from_args_after_option<'a>(args:&[String], option: &'a str) -> Result<Vec<String>,&'a str> {
     //move iterator to the specified option
     let next_arg;
     
     //get position of option in args
-    let pos;
-    match args.iter().position(|arg| arg.eq(&option)).ok_or("Could not find option in args.") {
-        Ok(index) => pos = index,
-        Err(e) => return Err(e),
-    }
+    let pos = args.iter().position(|arg| arg.eq(&option)).ok_or("Could not find option in args.")?;
 
     //if there is not an argument between it and the last argument
     if args.len() <= 2 || pos > args.len() - 2 {
@@ -140,11 +329,28 @@ fn get_list_from_args_after_option<'a>(args:&[String], option: &'a str) -> Resul
 
     //create and return the list
     return Ok(
-        next_arg.clone().chars().filter(|c| c.is_ascii_alphabetic() || c.eq(&',')).collect::<String>() //remove invalid characters
-        .split(",").filter_map(|s| (!s.is_empty()).then(|| s.to_ascii_lowercase())).collect() //split the string into vector at Commas, then remove empty values and convert to lowercase
+        next_arg.clone().chars().filter(|c| c.is_ascii_alphabetic() || ['+', '#', ','].contains(c)).collect::<String>() //remove invalid characters, + and # are kept for languages like c++ and c#
+        .split(',').filter(|s| !s.is_empty()).map(|s| s.to_ascii_lowercase()).collect() //split the string into vector at Commas, then remove empty values and convert to lowercase
     )
 }
 
+//private function that goes through the arguments to find the value after every occurrence of a specified option, for options that can be given more than once
+fn get_values_from_args_after_option(args:&[String], option: &str) -> Result<Vec<String>,&'static str> {
+    let mut values = Vec::new();
+    for (pos, _) in args.iter().enumerate().filter(|(_, arg)| arg.eq(&option)) {
+        //the last argument is the path, so the value can't be it
+        if pos + 2 >= args.len() {
+            return Err("Not enough arguments, Or no value found.");
+        }
+        let next_arg = &args[pos+1];
+        //throw an error if the "value" is actually an option
+        if VALID_OPTIONS.contains(&next_arg.as_str()) {
+            return Err("No value found.");
+        }
+        values.push(next_arg.clone());
+    }
+    return Ok(values);
+}
 
 
 /**
@@ -152,7 +358,8 @@ fn get_list_from_args_after_option<'a>(args:&[String], option: &'a str) -> Resul
  */
 pub fn run(config: Config) -> Result<(), Box<dyn Error>> {
     //DATA
-    let mut paths_to_process:Vec<PathBuf>; 
+    let start = Instant::now();
+    let mut totals = Totals::default();
     let path = PathBuf::from(&config.path);
 
     //if the user wants/needs help print instructions and exit
@@ -160,59 +367,117 @@ pub fn run(config: Config) -> Result<(), Box<dyn Error>> {
         help();
         return Ok(());
     }
+
+    let mut report = report::new_report(&config);
     
     // if path is a file
     if !config.path_is_directory {
-        let count = match count_lines_of_file(&config.path) {
-            Ok(c) => c,
-            Err(e) => return Err(e), 
-        };
-        println!("{}: {} Lines", config.path, count);
+        check_not_special(&path, config.read_fifo)?;
+        match count_file(&config.path, &config)? {
+            Some(count) => {
+                if !config.summary_only {
+                    report.file(&config.path, &count);
+                }
+                totals.add(&count);
+                if !passes_audit(&config.path, &count, &config) {
+                    totals.failed_audits += 1;
+                }
+            },
+            None => return Err(format!("{} looks like a binary file, pass --binary to count it anyway", config.path).into()),
+        }
     }
     //if path is a directory
     else  {
-        if config.search_subdirectories_recursively { //user wants us to search subdirectories recursively
-            paths_to_process = list_files_recursively(&path)
-        }
-        else { //user does not want us to search subdirectories recursively
-            paths_to_process = list_files(&path);
-        }
-
-        //if user want to filter for some given extensions, do that here
-        if !config.extensions_to_filter_for.is_empty() {
-            paths_to_process = paths_to_process.into_iter().filter(|raw_path| {
-                if let Some(extension) = raw_path.extension() {
-                    if config.extensions_to_filter_for.iter().map(|ext| OsString::from(ext)).any(|ext| ext.eq_ignore_ascii_case(extension)) {
-                        return true;
-                    }
-                }
-                return false;
-            }).collect();
-        } else {
+        let jobs = config.jobs.unwrap_or_else(parallel::default_jobs);
+        let walk_options = WalkOptions {
+            recursive: config.search_subdirectories_recursively,
+            honor_ignore_files: !config.no_ignore,
+            excludes: config.exclude_patterns.iter().filter_map(|p| PathPattern::new(p)).collect(),
+            includes: config.include_patterns.iter().filter_map(|p| PathPattern::new(p)).collect(),
+            symlinks: config.symlink_policy,
+            jobs,
+        };
+        //paths are counted as soon as the walk finds them, instead of waiting for it to finish
+        let paths_to_process = walk::walk_files(&path, walk_options);
+
+        //filters, and counts the lines of, one file
+        //filtering by language has to read the file, so this is done in parallel along with the counting
+        let filtering = !config.extensions_to_filter_for.is_empty() || !config.names_to_filter_for.is_empty();
+        let count_path = |entry: WalkEntry| -> Found {
+            let raw_path = match entry {
+                WalkEntry::File(raw_path) => raw_path,
+                WalkEntry::Skipped(skipped) => return Found::Skipped(skipped),
+                WalkEntry::Warning(warning) => return Found::Warning(warning),
+            };
+            //if user want to filter for some given extensions, languages, or file names, do that here
+            if filtering && !(matches_filter(&raw_path, &config.extensions_to_filter_for) || matches_name(&raw_path, &config.names_to_filter_for)) {
+                return Found::FilteredOut;
+            }
             //just filter out things that return None from .extension
-            paths_to_process = paths_to_process.into_iter().filter(|raw_path| raw_path.extension().is_some()).collect();
-        }
+            if config.require_extension && raw_path.extension().is_none() {
+                return Found::FilteredOut;
+            }
+            let path_name = match raw_path.into_os_string().into_string() { //convert it into a string
+                Ok(p) => p,
+                Err(_) => return Found::FilteredOut,
+            };
+            match count_file(&path_name, &config) {
+                Ok(Some(count)) => return Found::File(path_name, Box::new(count)),
+                Ok(None) => return Found::BinaryFile(path_name),
+                Err(e) => return Found::Error(path_name, e.to_string()),
+            }
+        };
 
-        //count lines of every file in paths_to_process
-        let mut i = 1; //counter variable for the Numberic format
-        for path_name in paths_to_process.into_iter().filter_map(|p| p.into_os_string().into_string().ok()) { //convert them all into strings
-            let count = match count_lines_of_file(&path_name) {
-                Ok(c) => c,
-                Err(e) => {eprintln!("!\t{}",e); continue;}, //just print errors to std. error, no use ending the program early 
+        //count lines of every file in paths_to_process, reporting them in the order they were found
+        parallel::map_in_order(paths_to_process, jobs, count_path, |found| {
+            let (path_name, count) = match found {
+                Found::File(path_name, count) => (path_name, count),
+                Found::FilteredOut => return,
+                Found::BinaryFile(path_name) => {
+                    if !config.summary_only {
+                        report.binary(&path_name);
+                    }
+                    totals.skipped_binary_files += 1;
+                    return;
+                },
+                //just print errors to std. error, no use ending the program early, the walk already skipped whatever caused them
+                Found::Error(path_name, e) => {
+                    eprintln!("!\t{}",e);
+                    if !config.summary_only {
+                        report.failed(&path_name, &e);
+                    }
+                    totals.failed_files += 1;
+                    return;
+                },
+                Found::Skipped(skipped) => {
+                    eprintln!("!\t{}", skipped);
+                    if !config.summary_only {
+                        report.warning(&skipped.path, &skipped.to_string());
+                    }
+                    totals.skipped_special_files += 1;
+                    return;
+                },
+                Found::Warning(warning) => {
+                    eprintln!("!\t{}", warning);
+                    if !config.summary_only {
+                        report.warning(&warning.path, &warning.to_string());
+                    }
+                    totals.traversal_errors += 1;
+                    return;
+                },
             };
 
-            //format output as specified by config.output_format
-            match config.output_format {
-                FORMAT::Default => print!("\t"),
-                FORMAT::Bullet => print!("*\t"),
-                FORMAT::Markdown => print!("-\t"),
-                FORMAT::Numeric => print!("{}.)\t", i),
+            if !config.summary_only {
+                report.file(&path_name, &count);
             }
-            println!("{}: {} Lines", path_name, count);
-
-            i+=1;
-        };
+            totals.add(&count);
+            if !passes_audit(&path_name, &count, &config) {
+                totals.failed_audits += 1;
+            }
+        });
     }
+    totals.elapsed = start.elapsed();
+    report.finish(&totals);
 
 
     //if path is a directory, and the -r argument was passed, run count_lines() on all files in it and subdirectories
@@ -220,29 +485,223 @@ pub fn run(config: Config) -> Result<(), Box<dyn Error>> {
     
     //if path is a file, run count_lines() on it
 
+    //in strict mode, anything going wrong while searching is an error
+    if config.strict && totals.traversal_errors > 0 {
+        return Err(format!("{} error(s) while searching {}", totals.traversal_errors, config.path).into());
+    }
+    if totals.failed_audits > 0 {
+        return Err(format!("{} file(s) failed the audit", totals.failed_audits).into());
+    }
+
     //return () if no issue
     return Ok(());
 }
 
+//everything counted about one file
+#[derive(Default)]
+struct FileCount {
+    lines: usize,
+    size: usize, //in bytes
+    line_stats: Option<LineStats>, //only if config.classify_lines is set, or the output is a markdown table
+    encoding: Encoding,
+    language: Option<&'static language::Language>,
+    hygiene: Option<Hygiene>, //only if config.audit is set
+    measurements: Measurements, //only what config.measures asks for
+    line_lengths: Option<LineLengths>, //only if config.line_stats or config.long_line_limit is set
+}
+
+//what happened to one thing the walk found, sent from the threads counting files to the one printing them
+enum Found {
+    File(String, Box<FileCount>), //boxed, it's much bigger than anything else here
+    FilteredOut,
+    BinaryFile(String),
+    Error(String, String), //couldn't be counted, the path and why
+    Skipped(walk::SkippedFile),
+    Warning(walk::TraversalWarning),
+}
+
+//how much of the start of a file is read before deciding how to count it
+const HEAD_SIZE: usize = 8192;
+
+//reads a file once and counts everything config asks for
+//returns None if the file looks binary and config.count_binary_files isn't set
+fn count_file(path: &str, config: &Config) -> Result<Option<FileCount>, Box<dyn Error>> {
+    let read_error = || format!("Could not read contents of {}", path);
+    let mut file = File::open(path).map_err(|_e| read_error())?;
+    let mut head = Vec::with_capacity(HEAD_SIZE);
+    file.by_ref().take(HEAD_SIZE as u64).read_to_end(&mut head).map_err(|_e| read_error())?;
+
+    let is_binary = binary::looks_binary(&head, &DecodeOptions { fallback: config.fallback_encoding, lossy: config.lossy });
+    if is_binary && !config.count_binary_files {
+        return Ok(None);
+    }
+    let options = DecodeOptions {
+        fallback: config.fallback_encoding,
+        lossy: config.lossy || is_binary, //binary files are never valid text, but the user asked for them to be counted anyway
+    };
+    let encoding = encoding::sniff(&head, &options);
+
+    let line_options = LineOptions { terminators: config.line_terminators, count_final_line: config.count_final_line };
+
+    //if all we need is the number of lines, stream the rest of the file through the line counter instead of decoding it
+    //text that can't be decoded is only caught if it's near the start of the file, since the rest is never decoded
+    //unicode line separators are more than one byte, so they're only found in decoded text
+    let head_decodes = options.lossy || options.fallback.is_some() || encoding != Encoding::Utf8 || encoding::is_utf8_prefix(&head);
+    let measure_lengths = config.line_stats || config.long_line_limit.is_some();
+    //the markdown table always has columns for code, comment, and blank lines
+    let classify = config.classify_lines || config.output_format == FORMAT::Markdown;
+    if !classify && !config.audit && !config.measures.need_text() && !measure_lengths && encoding.is_ascii_compatible() && head_decodes && config.line_terminators != LineTerminators::Unicode {
+        let mut counter = LineCounter::new(line_options);
+        counter.count(&head);
+        counter.count_file(&mut file).map_err(|_e| read_error())?;
+        //only the start of the file was kept, and a modeline can be at the end, so that's read again from the same file, if it's a regular one
+        let language = language::from_path(Path::new(path)).or_else(|| {
+            let tail = if counter.bytes() > head.len() { language::read_end(&mut file) } else { Vec::new() };
+            language::detect_in(Path::new(path), &String::from_utf8_lossy(&head), &String::from_utf8_lossy(&tail))
+        });
+        return Ok(Some(FileCount {
+            lines: counter.finish(),
+            size: counter.bytes(),
+            line_stats: None,
+            encoding,
+            hygiene: None,
+            measurements: measure::measure("", counter.bytes(), &config.measures),
+            line_lengths: None,
+            language,
+        }));
+    }
+
+    let mut bytes = head;
+    file.read_to_end(&mut bytes).map_err(|_e| read_error())?;
+    let (file_contents, encoding) = match encoding::decode(&bytes, &options) {
+        Ok(decoded) => decoded,
+        Err(e) => return Err(format!("Could not decode {}: {}", path, e).into()),
+    };
+
+    let language = language::detect(Path::new(path), &file_contents);
+    return Ok(Some(FileCount {
+        lines: count_lines_in_text(&file_contents, &line_options),
+        size: bytes.len(),
+        line_stats: classify.then(|| classify_lines(&file_contents, language, &line_options)),
+        encoding,
+        language,
+        hygiene: config.audit.then(|| audit::audit(&file_contents)),
+        measurements: measure::measure(&file_contents, bytes.len(), &config.measures),
+        line_lengths: measure_lengths.then(|| lengths::line_lengths(&file_contents, config.long_line_limit)),
+    }));
+}
+
+//checks a file against the checks config denies, printing whichever it fails to std. error
+fn passes_audit(path_name: &str, count: &FileCount, config: &Config) -> bool {
+    let failed = match &count.hygiene {
+        Some(hygiene) => hygiene.violations(&config.denied_checks),
+        None => return true,
+    };
+    if failed.is_empty() {
+        return true;
+    }
+    eprintln!("!\t{} failed: {}", path_name, failed.iter().map(|check| check.to_string()).collect::<Vec<String>>().join(", "));
+    return false;
+}
+
 /**
- * run count_lines on a given path
+ * reads and decodes the contents of a file, see encoding::decode for how the encoding is picked
+ * returns the text and the encoding it was decoded as
  */
-pub fn count_lines_of_file<'a>(path: &'a str) -> Result<usize, Box<dyn Error>> {
-    //count lines in path
-    let file_contents;
-    match fs::read_to_string(&path) {
-        Ok(s) => file_contents = s,
-        Err(_e) => return Err(format!("Could not read contents of {}", path).into()), //create and return an error with that message
+pub fn read_file(path: &str, options: &DecodeOptions) -> Result<(String, Encoding), Box<dyn Error>> {
+    let bytes = match fs::read(path) {
+        Ok(b) => b,
+        Err(_e) => return Err(format!("Could not read contents of {}", path).into()),
+    };
+    match encoding::decode(&bytes, options) {
+        Ok(decoded) => return Ok(decoded),
+        Err(e) => return Err(format!("Could not decode {}: {}", path, e).into()),
     }
+}
+/**
+ * count the lines of a given path
+ * unless it's UTF-16 or UTF-32, the file isn't decoded, large files are counted straight from a memory map,
+ * and anything else is streamed through a fixed size buffer, so even huge files don't use much memory
+ */
+pub fn count_lines_of_file(path: &str) -> Result<usize, Box<dyn Error>> {
+    let read_error = || format!("Could not read contents of {}", path);
+    let mut file = File::open(path).map_err(|_e| read_error())?;
+    let mut head = Vec::with_capacity(HEAD_SIZE);
+    file.by_ref().take(HEAD_SIZE as u64).read_to_end(&mut head).map_err(|_e| read_error())?;
+
+    if encoding::sniff(&head, &DecodeOptions::default()).is_ascii_compatible() {
+        let mut counter = LineCounter::new(LineOptions::default());
+        counter.count(&head);
+        counter.count_file(&mut file).map_err(|_e| read_error())?;
+        return Ok(counter.finish());
+    }
+    //any invalid characters are replaced since they can't be a newline anyway
+    let mut bytes = head;
+    file.read_to_end(&mut bytes).map_err(|_e| read_error())?;
+    let (file_contents, _) = encoding::decode(&bytes, &DecodeOptions { fallback: None, lossy: true })?;
     return Ok(count_lines(&file_contents));
 }
+/**
+ * run classify_lines on a given path, using the language detected from its name or contents
+ */
+pub fn classify_lines_of_file(path: &str) -> Result<LineStats, Box<dyn Error>> {
+    let (file_contents, _) = read_file(path, &DecodeOptions { fallback: None, lossy: true })?;
+    return Ok(classify_lines(&file_contents, language::detect(Path::new(path), &file_contents), &LineOptions::default()));
+}
+/**
+ * measure the length of every line of a given path, in display columns,
+ * and find the lines longer than column_limit, if there is one
+ */
+pub fn line_lengths_of_file(path: &str, column_limit: Option<usize>) -> Result<LineLengths, Box<dyn Error>> {
+    let (file_contents, _) = read_file(path, &DecodeOptions { fallback: None, lossy: true })?;
+    return Ok(lengths::line_lengths(&file_contents, column_limit));
+}
 /**
  * count number of newline characters in a given string
+ * a last line without a newline at the end isn't counted, see count_lines_in_text for other ways to count
  */
-pub fn count_lines<'a>(file_contents: &'a str) -> usize {
-    return file_contents.chars().filter(|c| *c == '\n').count();
+pub fn count_lines(file_contents: &str) -> usize {
+    //'\n' is always a single byte in UTF-8, and never part of another character
+    return count::count_newlines(file_contents.as_bytes());
+}
+
+//makes sure the path the user gave isn't a device or socket, which could block or never end
+//named pipes are only allowed if read_fifo is set
+fn check_not_special(path: &Path, read_fifo: bool) -> Result<(), Box<dyn Error>> {
+    let file_type = fs::metadata(path)?.file_type();
+    match walk::special_kind(&file_type) {
+        None => return Ok(()),
+        Some(SpecialKind::NamedPipe) if read_fifo => return Ok(()),
+        Some(SpecialKind::NamedPipe) => return Err(format!("{} is a named pipe, pass --read-fifo to read it anyway", path.display()).into()),
+        Some(kind) => return Err(format!("{} is a {}, not a file", path.display(), kind).into()),
+    }
+}
+
+//checks if a path has one of the extensions in filter, or is written in one of the languages named in filter
+fn matches_filter(path: &Path, filter: &[String]) -> bool {
+    if let Some(extension) = path.extension() {
+        if filter.iter().map(OsString::from).any(|ext| ext.eq_ignore_ascii_case(extension)) {
+            return true;
+        }
+    }
+    //only bother detecting the language if the filter actually names one
+    let languages: Vec<&language::Language> = filter.iter().filter_map(|name| language::from_name(name)).collect();
+    if languages.is_empty() {
+        return false;
+    }
+    return language::detect_file(path).is_some_and(|detected| languages.contains(&detected));
+}
+
+//checks if the file name of a path is exactly one of names, or matches one of them as a glob
+fn matches_name(path: &Path, names: &[String]) -> bool {
+    let file_name = match path.file_name().and_then(|name| name.to_str()) {
+        Some(name) => name,
+        None => return false,
+    };
+    return names.iter().any(|name| glob::glob_matches(name, file_name));
 }
 
+
 /***
  * print instructions
  */
@@ -254,68 +713,45 @@ pub fn help() {
     println!("USAGE:\n\tline-counter.exe [OPTIONS]... [PATH]\n");
 
     println!("OPTIONS:");
-    println!("\t-f\t--filter <EXTENSIONS>...\t\tComma separated list of extensions, will only count lines of files with these extensions");
-    println!("\t\t--format=[FORMAT]\t\t\tFormat the output in a list, valid formats are: DEFAULT, BULLET, MARKDOWN, and NUMERIC");
+    println!("\t-f\t--filter <EXTENSIONS>...\t\tComma separated list of extensions or languages, will only count lines of files with these extensions or written in these languages");
+    println!("\t\t--name <NAME>\t\t\t\tOnly count files with this exact name or matching this glob, like Makefile or *.in, can be given more than once");
+    println!("\t\t--require-extension\t\t\tSkip files without an extension");
+    println!("\t\t--format=[FORMAT]\t\t\tHow to format the output, valid formats are: DEFAULT, BULLET, MARKDOWN, NUMERIC, JSON, NDJSON, CSV, TSV, HTML, and SVG-TREEMAP");
     println!("\t-r,\t--recursive\t\t\t\tSearch through subdirectories");
+    println!("\t\t--no-ignore\t\t\t\tDon't skip files matched by .gitignore, .ignore, .git/info/exclude, or core.excludesFile");
+    println!("\t\t--exclude <GLOB>\t\t\tSkip files and directories matching this glob, like vendor/ or src/**/*.gen.rs, can be given more than once");
+    println!("\t\t--include <GLOB>\t\t\tOnly count files matching this glob, or in a directory matching it, can be given more than once");
+    println!("\t\t--strict\t\t\t\tExit with an error if any directory or file couldn't be searched");
+    println!("\t\t--read-fifo\t\t\t\tRead PATH even if it's a named pipe, other special files are always skipped");
+    println!("\t\t--binary\t\t\t\tCount files that look like binary files, instead of skipping them");
+    println!("\t\t--fallback-encoding <ENCODING>\t\tHow to read files that aren't UTF-8 and have no byte order mark, like LATIN-1, WINDOWS-1252, or UTF-16LE");
+    println!("\t\t--lossy\t\t\t\t\tReplace anything that can't be decoded, instead of skipping the file");
+    println!("\t\t--show-encoding\t\t\t\tPrint the encoding each file was read as");
+    println!("\t\t--symlinks=[POLICY]\t\t\tWhich symlinks to follow, valid policies are: NEVER, FOLLOW (the default), and WITHIN-ROOT");
+    println!("\t\t--line-endings=[ENDINGS]\t\tWhat ends a line, valid endings are: LF (the default), ANY (LF, CRLF, or CR), and UNICODE (ANY, plus VT, FF, NEL, LS, and PS)");
+    println!("\t\t--count-final-line\t\t\tAlso count the last line of a file if it doesn't end with a line ending, like editors do");
+    println!("\t\t--audit\t\t\t\t\tReport the line endings, trailing whitespace, mixed indentation, and missing final newline of each file");
+    println!("\t\t--deny <CHECKS>\t\t\t\tComma separated list of checks that make the audit fail, valid checks are: LF, CRLF, CR, MIXED-LINE-ENDINGS, TRAILING-WHITESPACE, MIXED-INDENTATION, and NO-FINAL-NEWLINE");
+    println!("\t\t--bytes\t\t\t\t\tAlso count bytes");
+    println!("\t-m,\t--chars\t\t\t\t\tAlso count characters (Unicode scalar values)");
+    println!("\t\t--graphemes\t\t\t\tAlso count grapheme clusters, what a reader would call characters");
+    println!("\t-w,\t--words\t\t\t\t\tAlso count words separated by whitespace");
+    println!("\t-L,\t--max-line-length\t\t\tAlso find the longest line, in bytes and in display columns");
+    println!("\t\t--line-stats\t\t\t\tPrint the min, max, mean, and percentile line lengths of each file, and of all of them together");
+    println!("\t\t--long-lines <COLUMNS>\t\t\tList every line longer than this many columns, as path:line");
+    println!("\t-c,\t--classify\t\t\t\tAlso count code, comment, and blank lines, based on the language of each file");
+    println!("\t-g,\t--group-by-language\t\t\tAfter counting, print the total files and lines for each language");
+    println!("\t-j,\t--jobs <N>\t\t\t\tHow many threads search directories and count files at once, defaults to the number of CPUs, output is always in the same order");
+    println!("\t\t--no-summary\t\t\t\tDon't print the total files, lines, skipped and failed files, and time taken at the end");
+    println!("\t\t--summary-only\t\t\t\tOnly print the totals at the end, not every file");
     println!("\t-h,\t-help\t\t\t\t\tPrints help information\n");
 
     println!("PATH:\n\tPath to search\n\n")
 }
 
-/**
- * returns a vector containing paths to all files in path and subdirectories of path
- */
-fn list_files_recursively(path: &Path) -> Vec<PathBuf> {
-    let mut vec = Vec::new();
-    _list_files_recursively(&mut vec,&path);
-    vec
-}
-fn _list_files_recursively(vec: &mut Vec<PathBuf>, path: &Path) {
-    if path.is_dir() {
-        let paths = fs::read_dir(&path).unwrap();
-        for path_result in paths {
-            let full_path = path_result.unwrap().path();
-            if full_path.is_dir() {
-                _list_files_recursively(vec, &full_path);
-            } else {
-                vec.push(full_path);
-            }
-        }
-    }
-}
-/**
- * returns a vector containing paths to all files in path, but not subdirectories of path
- */
-fn list_files(path: &Path) -> Vec<PathBuf> {
-    let mut vec = Vec::new();
-    if path.is_dir() {
-        let paths = fs::read_dir(&path).unwrap();
-        for path_results in paths {
-            let full_path = path_results.unwrap().path();
-            if !full_path.is_dir() {
-                vec.push(full_path);
-            }
-        }
-    }
-    return vec;
-}
-
-
-
-
-
-
-
-
-
-
-
-
-
-
-
 //tests
 #[cfg(test)]
+#[allow(clippy::useless_vec)]
 mod tests {
     use std::vec;
 
@@ -333,6 +769,7 @@ mod tests {
             output_format: FORMAT::Default,
             search_subdirectories_recursively:false,
             show_help:true,
+            ..Config::default()
         };
         let actual_config = Config::new(&args).expect("test resulted in error creating config");
 
@@ -348,6 +785,7 @@ mod tests {
             output_format: FORMAT::Numeric,
             search_subdirectories_recursively:true,
             show_help:false,
+            ..Config::default()
         };
         let actual_config = Config::new(&args).expect("test resulted in error creating config");
 
@@ -363,6 +801,7 @@ mod tests {
             output_format: FORMAT::Markdown,
             search_subdirectories_recursively:true,
             show_help:false,
+            ..Config::default()
         };
         let actual_config = Config::new(&args).expect("test resulted in error creating config");
 
@@ -378,6 +817,7 @@ mod tests {
             output_format: FORMAT::Default,
             search_subdirectories_recursively:false,
             show_help:true,
+            ..Config::default()
         };
         let actual_config = Config::new(&args).expect("test resulted in error creating config");
 
@@ -395,6 +835,7 @@ mod tests {
             output_format: FORMAT::Default,
             search_subdirectories_recursively:false,
             show_help:true,
+            ..Config::default()
         };
         let actual_config = Config::new(&args).expect("test resulted in error creating config");
 
@@ -411,6 +852,7 @@ mod tests {
             output_format: FORMAT::Default,
             search_subdirectories_recursively:false,
             show_help:false,
+            ..Config::default()
         };
         let actual_config = Config::new(&args).expect("test resulted in error creating config");
 
@@ -441,6 +883,7 @@ mod tests {
             output_format: FORMAT::Default,
             search_subdirectories_recursively:false,
             show_help:false,
+            ..Config::default()
         };
         let actual_config = Config::new(&args).expect("test resulted in error creating config");
 
@@ -457,6 +900,7 @@ mod tests {
             output_format: FORMAT::Default,
             search_subdirectories_recursively:true,
             show_help:false,
+            ..Config::default()
         };
         let actual_config = Config::new(&args).expect("test resulted in error creating config");
 
@@ -473,6 +917,7 @@ mod tests {
             output_format: FORMAT::Default,
             search_subdirectories_recursively:true,
             show_help:false,
+            ..Config::default()
         };
         let actual_config = Config::new(&args).expect("test resulted in error creating config");
 
@@ -489,6 +934,7 @@ mod tests {
             output_format: FORMAT::Bullet,
             search_subdirectories_recursively:false,
             show_help:false,
+            ..Config::default()
         };
         let actual_config = Config::new(&args).expect("test resulted in error creating config");
 
@@ -505,6 +951,7 @@ mod tests {
             output_format: FORMAT::Markdown,
             search_subdirectories_recursively:false,
             show_help:false,
+            ..Config::default()
         };
         let actual_config = Config::new(&args).expect("test resulted in error creating config");
 
@@ -521,6 +968,125 @@ mod tests {
             output_format: FORMAT::Numeric,
             search_subdirectories_recursively:false,
             show_help:false,
+            ..Config::default()
+        };
+        let actual_config = Config::new(&args).expect("test resulted in error creating config");
+
+        assert_eq!(expected_config, actual_config);
+    }
+    #[test]
+    fn config_format_json() {
+        let args: Vec<String> = ["--format=JSON", "-r", "../"].iter().map(|s| s.to_string()).collect();
+
+        let expected_config: Config = Config{
+            path: String::from("../"),
+            path_is_directory:true,
+            output_format: FORMAT::Json,
+            search_subdirectories_recursively:true,
+            ..Config::default()
+        };
+        let actual_config = Config::new(&args).expect("test resulted in error creating config");
+
+        assert_eq!(expected_config, actual_config);
+
+        let args: Vec<String> = ["--format=NDJSON", "../"].iter().map(|s| s.to_string()).collect();
+        let actual_config = Config::new(&args).expect("test resulted in error creating config");
+        assert_eq!(actual_config.output_format, FORMAT::Ndjson);
+    }
+    #[test]
+    fn config_format_delimited() {
+        let args: Vec<String> = ["--format=CSV", "../"].iter().map(|s| s.to_string()).collect();
+        let actual_config = Config::new(&args).expect("test resulted in error creating config");
+        assert_eq!(actual_config.output_format, FORMAT::Csv);
+
+        let args: Vec<String> = ["--format=TSV", "../"].iter().map(|s| s.to_string()).collect();
+        let actual_config = Config::new(&args).expect("test resulted in error creating config");
+        assert_eq!(actual_config.output_format, FORMAT::Tsv);
+    }
+    #[test]
+    fn config_format_html() {
+        let args: Vec<String> = ["--format=HTML", "-r", "-c", "../"].iter().map(|s| s.to_string()).collect();
+
+        let expected_config: Config = Config{
+            path: String::from("../"),
+            path_is_directory:true,
+            output_format: FORMAT::Html,
+            search_subdirectories_recursively:true,
+            classify_lines:true,
+            ..Config::default()
+        };
+        let actual_config = Config::new(&args).expect("test resulted in error creating config");
+
+        assert_eq!(expected_config, actual_config);
+
+        let args: Vec<String> = ["--format=SVG-TREEMAP", "../"].iter().map(|s| s.to_string()).collect();
+        let actual_config = Config::new(&args).expect("test resulted in error creating config");
+        assert_eq!(actual_config.output_format, FORMAT::SvgTreemap);
+    }
+    #[test]
+    fn config_summary_flags() {
+        let args: Vec<String> = ["--no-summary", "../"].iter().map(|s| s.to_string()).collect();
+        let actual_config = Config::new(&args).expect("test resulted in error creating config");
+        assert!(actual_config.no_summary && !actual_config.summary_only);
+
+        let args: Vec<String> = ["--summary-only", "../"].iter().map(|s| s.to_string()).collect();
+        let actual_config = Config::new(&args).expect("test resulted in error creating config");
+        assert!(actual_config.summary_only && !actual_config.no_summary);
+
+        //they can't both be given
+        let args: Vec<String> = ["--no-summary", "--summary-only", "../"].iter().map(|s| s.to_string()).collect();
+        assert!(Config::new(&args).is_err());
+    }
+    #[test]
+    fn config_classify_flags() {
+        for flag in ["-c", "--classify"] {
+            let args: Vec<String> = [flag, "../"].iter().map(|s| s.to_string()).collect();
+            let expected_config: Config = Config{
+                path: String::from("../"),
+                path_is_directory:true,
+                classify_lines:true,
+                ..Config::default()
+            };
+            let actual_config = Config::new(&args).expect("test resulted in error creating config");
+
+            assert_eq!(expected_config, actual_config);
+        }
+    }
+    #[test]
+    fn config_group_by_language_flags() {
+        for flag in ["-g", "--group-by-language"] {
+            let args: Vec<String> = [flag, "-f", "rust,python", "../"].iter().map(|s| s.to_string()).collect();
+            let expected_config: Config = Config{
+                path: String::from("../"),
+                path_is_directory:true,
+                extensions_to_filter_for: vec!["rust".to_string(),"python".to_string()],
+                group_by_language:true,
+                ..Config::default()
+            };
+            let actual_config = Config::new(&args).expect("test resulted in error creating config");
+
+            assert_eq!(expected_config, actual_config);
+        }
+    }
+    #[test]
+    fn filter_by_extension_or_language() {
+        let filter = vec!["rust".to_string(), "toml".to_string()];
+        assert!(matches_filter(Path::new("src/lib.rs"), &filter));
+        assert!(matches_filter(Path::new("Cargo.toml"), &filter));
+        assert!(!matches_filter(Path::new("README"), &filter));
+
+        let filter = vec!["exe".to_string()];
+        assert!(!matches_filter(Path::new("src/lib.rs"), &filter));
+    }
+    #[test]
+    fn config_names_and_require_extension() {
+        let args: Vec<String> = ["--name", "Makefile", "--require-extension", "--name", "*.in", "../"].iter().map(|s| s.to_string()).collect();
+        let expected_config: Config = Config{
+            path: String::from("../"),
+            path_is_directory:true,
+            names_to_filter_for: vec!["Makefile".to_string(),"*.in".to_string()],
+            require_extension:true,
+            ..Config::default()
         };
         let actual_config = Config::new(&args).expect("test resulted in error creating config");
 
@@ -528,6 +1094,258 @@ mod tests {
     }
     #[test]
     #[should_panic]
+    fn config_name_without_value() {
+        let args: Vec<String> = ["--name", "../"].iter().map(|s| s.to_string()).collect();
+        //should panic here bc --name needs a value before the path
+        let _actual_config = Config::new(&args).expect("test resulted in error creating config");
+    }
+    #[test]
+    fn filter_by_name() {
+        let names = vec!["Makefile".to_string(), "Docker*".to_string()];
+        assert!(matches_name(Path::new("project/Makefile"), &names));
+        assert!(matches_name(Path::new("Dockerfile"), &names));
+        assert!(!matches_name(Path::new("LICENSE"), &names));
+    }
+    #[test]
+    fn config_no_ignore() {
+        let args: Vec<String> = ["-r", "--no-ignore", "../"].iter().map(|s| s.to_string()).collect();
+        let expected_config: Config = Config{
+            path: String::from("../"),
+            path_is_directory:true,
+            search_subdirectories_recursively:true,
+            no_ignore:true,
+            ..Config::default()
+        };
+        let actual_config = Config::new(&args).expect("test resulted in error creating config");
+
+        assert_eq!(expected_config, actual_config);
+    }
+    #[test]
+    fn config_exclude_and_include() {
+        let args: Vec<String> = ["--exclude", "vendor/", "-r", "--include", "**/*.rs", "--exclude", "/target", "../"].iter().map(|s| s.to_string()).collect();
+        let expected_config: Config = Config{
+            path: String::from("../"),
+            path_is_directory:true,
+            search_subdirectories_recursively:true,
+            exclude_patterns: vec!["vendor/".to_string(),"/target".to_string()],
+            include_patterns: vec!["**/*.rs".to_string()],
+            ..Config::default()
+        };
+        let actual_config = Config::new(&args).expect("test resulted in error creating config");
+
+        assert_eq!(expected_config, actual_config);
+    }
+    #[test]
+    fn config_strict() {
+        let args: Vec<String> = ["--strict", "../"].iter().map(|s| s.to_string()).collect();
+        let expected_config: Config = Config{
+            path: String::from("../"),
+            path_is_directory:true,
+            strict:true,
+            ..Config::default()
+        };
+        let actual_config = Config::new(&args).expect("test resulted in error creating config");
+
+        assert_eq!(expected_config, actual_config);
+    }
+    #[test]
+    fn config_symlinks() {
+        for (flag, policy) in [("--symlinks=NEVER", SymlinkPolicy::Never), ("--symlinks=FOLLOW", SymlinkPolicy::Follow), ("--symlinks=WITHIN-ROOT", SymlinkPolicy::WithinRoot)] {
+            let args: Vec<String> = ["-r", flag, "../"].iter().map(|s| s.to_string()).collect();
+            let expected_config: Config = Config{
+                path: String::from("../"),
+                path_is_directory:true,
+                search_subdirectories_recursively:true,
+                symlink_policy:policy,
+                ..Config::default()
+            };
+            let actual_config = Config::new(&args).expect("test resulted in error creating config");
+
+            assert_eq!(expected_config, actual_config);
+        }
+    }
+    #[cfg(unix)]
+    #[test]
+    fn refuse_special_files() {
+        let socket_path = std::env::temp_dir().join(format!("line-counter-socket-{}", std::process::id()));
+        let _socket = std::os::unix::net::UnixListener::bind(&socket_path).unwrap();
+
+        assert!(check_not_special(&socket_path, true).is_err());
+        assert!(check_not_special(Path::new("src/lib.rs"), false).is_ok());
+
+        fs::remove_file(&socket_path).unwrap();
+    }
+    #[cfg(unix)]
+    #[test]
+    fn count_named_pipe() {
+        let pipe = std::env::temp_dir().join(format!("line-counter-fifo-{}", std::process::id()));
+        let _ = fs::remove_file(&pipe);
+        assert!(std::process::Command::new("mkfifo").arg(&pipe).status().unwrap().success());
+        let writer = {
+            let pipe = pipe.clone();
+            std::thread::spawn(move || fs::write(pipe, "#!/bin/sh\necho hi\n# vim: ft=sh\n").unwrap())
+        };
+
+        //the pipe can only be read once, so its language has to come from what was already read
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let path = pipe.to_str().unwrap().to_string();
+        std::thread::spawn(move || {
+            let count = count_file(&path, &Config { read_fifo: true, ..Config::default() }).unwrap().unwrap();
+            let _ = sender.send((count.lines, count.language.map(|l| l.name)));
+        });
+        let counted = receiver.recv_timeout(std::time::Duration::from_secs(10)).expect("counting a named pipe hung");
+        writer.join().unwrap();
+        assert_eq!(counted, (3, Some("Shell")));
+
+        fs::remove_file(&pipe).unwrap();
+    }
+    #[test]
+    fn count_files_in_fallback_encodings() {
+        let directory = std::env::temp_dir().join(format!("line-counter-fallback-{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        //UTF-16LE without a byte order mark, which is full of NULs
+        let utf16le = directory.join("utf16le.txt");
+        fs::write(&utf16le, "one\ntwo\nthree\n".encode_utf16().flat_map(|unit| unit.to_le_bytes()).collect::<Vec<u8>>()).unwrap();
+        //Windows-1252 with mostly non ASCII characters, which is far from valid UTF-8
+        let cp1252 = directory.join("cp1252.txt");
+        fs::write(&cp1252, b"\x93\xe9t\xe9\x94\n\xe0 \xe7\xe0\n").unwrap();
+
+        let count = |path: &Path, fallback: Option<Encoding>| {
+            let config = Config { fallback_encoding: fallback, ..Config::default() };
+            return count_file(path.to_str().unwrap(), &config).unwrap();
+        };
+        assert!(count(&utf16le, None).is_none());
+        let utf16le_count = count(&utf16le, Some(Encoding::Utf16Le)).expect("UTF-16LE file was skipped as binary");
+        assert_eq!((utf16le_count.encoding, utf16le_count.lines), (Encoding::Utf16Le, 3));
+        assert!(count(&cp1252, None).is_none());
+        let cp1252_count = count(&cp1252, Some(Encoding::Windows1252)).expect("Windows-1252 file was skipped as binary");
+        assert_eq!((cp1252_count.encoding, cp1252_count.lines), (Encoding::Windows1252, 2));
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+    #[test]
+    fn config_binary() {
+        let args: Vec<String> = ["--binary", "../"].iter().map(|s| s.to_string()).collect();
+        let expected_config: Config = Config{
+            path: String::from("../"),
+            path_is_directory:true,
+            count_binary_files:true,
+            ..Config::default()
+        };
+        let actual_config = Config::new(&args).expect("test resulted in error creating config");
+
+        assert_eq!(expected_config, actual_config);
+    }
+    #[test]
+    fn config_encoding() {
+        let args: Vec<String> = ["--fallback-encoding", "latin-1", "--lossy", "--show-encoding", "../"].iter().map(|s| s.to_string()).collect();
+        let expected_config: Config = Config{
+            path: String::from("../"),
+            path_is_directory:true,
+            fallback_encoding:Some(Encoding::Latin1),
+            lossy:true,
+            show_encoding:true,
+            ..Config::default()
+        };
+        let actual_config = Config::new(&args).expect("test resulted in error creating config");
+
+        assert_eq!(expected_config, actual_config);
+    }
+    #[test]
+    #[should_panic]
+    fn config_unknown_encoding() {
+        let args: Vec<String> = ["--fallback-encoding", "klingon", "../"].iter().map(|s| s.to_string()).collect();
+        //should panic here bc there's no such encoding
+        let _actual_config = Config::new(&args).expect("test resulted in error creating config");
+    }
+    #[test]
+    fn config_line_endings() {
+        let args: Vec<String> = ["--line-endings=ANY", "--count-final-line", "../"].iter().map(|s| s.to_string()).collect();
+        let expected_config: Config = Config {
+            path: "../".to_string(),
+            path_is_directory: true,
+            line_terminators: LineTerminators::Any,
+            count_final_line: true,
+            ..Config::default()
+        };
+        let actual_config = Config::new(&args).expect("test resulted in error creating config");
+        assert_eq!(actual_config, expected_config);
+
+        let args: Vec<String> = ["--line-endings=UNICODE", "../"].iter().map(|s| s.to_string()).collect();
+        let actual_config = Config::new(&args).expect("test resulted in error creating config");
+        assert_eq!(actual_config.line_terminators, LineTerminators::Unicode);
+        assert!(!actual_config.count_final_line);
+    }
+    #[test]
+    fn config_audit() {
+        let args: Vec<String> = ["--audit", "../"].iter().map(|s| s.to_string()).collect();
+        let actual_config = Config::new(&args).expect("test resulted in error creating config");
+        assert!(actual_config.audit);
+        assert!(actual_config.denied_checks.is_empty());
+
+        //--deny turns on the audit by itself
+        let args: Vec<String> = ["--deny", "crlf,TRAILING-WHITESPACE", "--deny", "no-final-newline", "../"].iter().map(|s| s.to_string()).collect();
+        let actual_config = Config::new(&args).expect("test resulted in error creating config");
+        assert!(actual_config.audit);
+        assert_eq!(actual_config.denied_checks, vec![Check::Crlf, Check::TrailingWhitespace, Check::NoFinalNewline]);
+
+        let args: Vec<String> = ["--deny", "tabs", "../"].iter().map(|s| s.to_string()).collect();
+        assert!(Config::new(&args).is_err());
+    }
+    #[test]
+    fn config_measures() {
+        let args: Vec<String> = ["-w", "--chars", "-L", "../"].iter().map(|s| s.to_string()).collect();
+        let expected_config: Config = Config {
+            path: "../".to_string(),
+            path_is_directory: true,
+            measures: Measures { words: true, chars: true, max_line_length: true, ..Measures::default() },
+            ..Config::default()
+        };
+        let actual_config = Config::new(&args).expect("test resulted in error creating config");
+        assert_eq!(actual_config, expected_config);
+
+        let args: Vec<String> = ["--bytes", "--graphemes", "-m", "--words", "--max-line-length", "../"].iter().map(|s| s.to_string()).collect();
+        let actual_config = Config::new(&args).expect("test resulted in error creating config");
+        assert_eq!(actual_config.measures, Measures { bytes: true, chars: true, graphemes: true, words: true, max_line_length: true });
+    }
+    #[test]
+    fn config_line_lengths() {
+        let args: Vec<String> = ["--line-stats", "--long-lines", "100", "../"].iter().map(|s| s.to_string()).collect();
+        let actual_config = Config::new(&args).expect("test resulted in error creating config");
+        assert!(actual_config.line_stats);
+        assert_eq!(actual_config.long_line_limit, Some(100));
+
+        let args: Vec<String> = ["--long-lines", "wide", "../"].iter().map(|s| s.to_string()).collect();
+        assert!(Config::new(&args).is_err());
+
+        //formats that have nowhere to put them
+        for format in ["--format=JSON", "--format=NDJSON", "--format=BULLET"] {
+            let args: Vec<String> = [format, "--line-stats", "--long-lines", "100", "../"].iter().map(|s| s.to_string()).collect();
+            assert!(Config::new(&args).is_ok(), "{} was rejected", format);
+        }
+        for format in ["--format=MARKDOWN", "--format=CSV", "--format=TSV", "--format=HTML", "--format=SVG-TREEMAP"] {
+            let args: Vec<String> = [format, "--line-stats", "../"].iter().map(|s| s.to_string()).collect();
+            assert!(Config::new(&args).is_err(), "{} was allowed", format);
+        }
+        let args: Vec<String> = ["--format=CSV", "--long-lines", "100", "../"].iter().map(|s| s.to_string()).collect();
+        assert!(Config::new(&args).is_ok());
+        let args: Vec<String> = ["--format=HTML", "--long-lines", "100", "../"].iter().map(|s| s.to_string()).collect();
+        assert!(Config::new(&args).is_err());
+    }
+    #[test]
+    fn config_jobs() {
+        let args: Vec<String> = ["--jobs", "4", "-r", "../"].iter().map(|s| s.to_string()).collect();
+        let actual_config = Config::new(&args).expect("test resulted in error creating config");
+        assert_eq!(actual_config.jobs, Some(4));
+        assert!(actual_config.search_subdirectories_recursively);
+
+        let args: Vec<String> = ["-j", "0", "../"].iter().map(|s| s.to_string()).collect();
+        assert!(Config::new(&args).is_err());
+        let args: Vec<String> = ["-j", "many", "../"].iter().map(|s| s.to_string()).collect();
+        assert!(Config::new(&args).is_err());
+    }
+    #[test]
+    #[should_panic]
     fn config_mixed_casing() {
         let args: Vec<String> = vec!["-F","eXe,rs", "--forMAt=numMERIC","-r","../"].iter().map(|s| s.to_string()).collect();
         //this should panic because of the improper capitalization
@@ -562,4 +1380,14 @@ Donec id pretium sapien, eu pharetra neque. In tempus tortor in congue cursus.";
 
         assert_eq!(count_lines(text), 6);
     }
+    #[test]
+    fn search_final_line_and_other_line_endings() {
+        //the same 7 lines as above, which an editor would show as 7
+        let text = "Lorem ipsum dolor sit amet.\nNullam vitae suscipit ipsum.\r\nEtiam pulvinar.\rNam nec leo diam.\nSuspendisse lectus dolor.\r\nNam sollicitudin.\nDonec id pretium sapien.";
+        assert_eq!(count_lines(text), 5);
+        assert_eq!(count_lines_in_text(text, &LineOptions { terminators: LineTerminators::Lf, count_final_line: true }), 6);
+        assert_eq!(count_lines_in_text(text, &LineOptions { terminators: LineTerminators::Any, count_final_line: false }), 6);
+        assert_eq!(count_lines_in_text(text, &LineOptions { terminators: LineTerminators::Any, count_final_line: true }), 7);
+        assert_eq!(count_lines_in_text(&text.replace("\rNam", "\u{2028}Nam"), &LineOptions { terminators: LineTerminators::Unicode, count_final_line: true }), 7);
+    }
 }
\ No newline at end of file
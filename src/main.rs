@@ -3,8 +3,7 @@ use std::{
     process, //allows for some better error handling
 };
 
-mod lib;
-use crate::lib::Config;
+use line_counter::{self as lib, Config};
 
 fn main() {
     //read command line arguments
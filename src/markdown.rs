@@ -0,0 +1,175 @@
+use crate::{
+    delimited::Column,
+    measure::display_width,
+    report::{show_summary, Report, Totals},
+    Config, FileCount,
+};
+
+/**
+ * the columns of the table, based on what config counts
+ * lines are always classified for the table, the encoding is only shown with config.show_encoding,
+ * and errors go to std. error instead of a column
+ */
+pub fn columns_for_config(config: &Config) -> Vec<Column> {
+    let mut columns: Vec<Column> = Column::for_config(config).into_iter().filter(|column| match column {
+        Column::Encoding => config.show_encoding,
+        Column::Size | Column::Error => false,
+        _ => true,
+    }).collect();
+    if !config.classify_lines {
+        let after_lines = columns.iter().position(|column| *column == Column::Lines).map_or(columns.len(), |i| i + 1);
+        columns.splice(after_lines..after_lines, [Column::Code, Column::Comments, Column::Blank]);
+    }
+    return columns;
+}
+
+/**
+ * escapes text so it shows up as is in a table cell of GitHub flavored markdown
+ * a | would end the cell, and the rest would be read as formatting
+ */
+pub fn escape_cell(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' | '|' | '`' | '*' | '_' | '~' | '[' | ']' | '<' | '>' => {
+                escaped.push('\\');
+                escaped.push(c);
+            },
+            '\r' | '\n' => escaped.push(' '), //a cell can't span lines
+            c => escaped.push(c),
+        }
+    }
+    return escaped;
+}
+
+/**
+ * lays out rows as a table, padding every cell so the columns line up in plain text too
+ * columns is the header of each column, and whether it's right aligned
+ */
+pub fn table(columns: &[(&str, bool)], rows: &[Vec<String>]) -> String {
+    //every column is at least 3 wide, since that's the shortest delimiter row markdown allows
+    let mut widths: Vec<usize> = columns.iter().map(|(name, _)| display_width(name).max(3)).collect();
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(display_width(cell));
+        }
+    }
+
+    let line = |cells: &mut dyn Iterator<Item = (&str, bool)>| -> String {
+        let cells: Vec<String> = cells.zip(&widths).map(|((cell, right), width)| {
+            let padding = " ".repeat(width - display_width(cell));
+            if right { padding + cell } else { cell.to_string() + &padding }
+        }).collect();
+        return format!("| {} |\n", cells.join(" | "));
+    };
+
+    let mut out = line(&mut columns.iter().copied());
+    let delimiters: Vec<String> = columns.iter().zip(&widths).map(|((_, right), width)| {
+        if *right { "-".repeat(width - 1) + ":" } else { "-".repeat(*width) }
+    }).collect();
+    out += &format!("| {} |\n", delimiters.join(" | "));
+    for row in rows {
+        out += &line(&mut row.iter().map(|cell| cell.as_str()).zip(columns.iter().map(|(_, right)| *right)));
+    }
+    return out;
+}
+
+/**
+ * --format=MARKDOWN, a table of every file, with a totals row and a summary under it if a directory was searched
+ * the whitespace problems found by config.audit, and lines longer than config.long_line_limit, are listed under the table
+ * the columns can only be lined up once every file is counted, so the table is written at the end
+ */
+pub struct MarkdownReport<'a> {
+    config: &'a Config,
+    columns: Vec<Column>,
+    rows: Vec<Vec<String>>,
+    hygiene: Vec<String>, //a list item for each file that was audited
+    long_lines: Vec<String>, //a list item for each line that's too long
+}
+impl MarkdownReport<'_> {
+    pub fn new(config: &Config) -> MarkdownReport<'_> {
+        return MarkdownReport { config, columns: columns_for_config(config), rows: Vec::new(), hygiene: Vec::new(), long_lines: Vec::new() };
+    }
+}
+impl Report for MarkdownReport<'_> {
+    fn file(&mut self, path: &str, count: &FileCount) {
+        let row = self.columns.iter().map(|column| match column {
+            Column::Path => escape_cell(path),
+            Column::Language => escape_cell(count.language.map_or("Unknown", |l| l.name)),
+            column => escape_cell(&column.value(path, count)),
+        }).collect();
+        self.rows.push(row);
+        if let Some(hygiene) = &count.hygiene {
+            self.hygiene.push(format!("- {}: {}", escape_cell(path), hygiene));
+        }
+        if let Some(line_lengths) = &count.line_lengths {
+            for long_line in &line_lengths.long_lines {
+                self.long_lines.push(format!("- {}:{}: {} columns", escape_cell(path), long_line.line_number, long_line.columns));
+            }
+        }
+    }
+
+    fn finish(&mut self, totals: &Totals) {
+        let summary = format!("**Total:** {}", totals.summary());
+        if self.config.summary_only {
+            println!("{}", summary);
+            return;
+        }
+        if show_summary(self.config) {
+            self.rows.push(self.columns.iter().map(|column| match column {
+                Column::Path => "**Total**".to_string(),
+                column => column.total(totals),
+            }).collect());
+        }
+        let header: Vec<(&str, bool)> = self.columns.iter().map(|column| (column.title(), column.is_number())).collect();
+        print!("{}", table(&header, &self.rows));
+        if !self.hygiene.is_empty() {
+            println!("\n**Whitespace:**\n\n{}", self.hygiene.join("\n"));
+        }
+        if let Some(limit) = self.config.long_line_limit {
+            println!("\n**Lines longer than {} columns:** {}", limit, totals.long_lines);
+            if !self.long_lines.is_empty() {
+                println!("\n{}", self.long_lines.join("\n"));
+            }
+        }
+        if show_summary(self.config) {
+            println!("\n{}", summary);
+        }
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escape_cells() {
+        assert_eq!(escape_cell("src/main.rs"), "src/main.rs");
+        assert_eq!(escape_cell("a|b_c*.rs"), "a\\|b\\_c\\*.rs");
+        assert_eq!(escape_cell("C:\\src\\main.rs"), "C:\\\\src\\\\main.rs");
+        assert_eq!(escape_cell("two\nlines"), "two lines");
+    }
+    #[test]
+    fn columns_follow_config() {
+        let headers = |config: &Config| columns_for_config(config).into_iter().map(|c| c.title()).collect::<Vec<_>>();
+        assert_eq!(headers(&Config::default()), ["File", "Language", "Lines", "Code", "Comments", "Blanks"]);
+        let args: Vec<String> = ["-w", "--show-encoding", "../"].iter().map(|s| s.to_string()).collect();
+        let config = Config::new(&args).unwrap();
+        assert_eq!(headers(&config), ["File", "Language", "Encoding", "Lines", "Code", "Comments", "Blanks", "Words"]);
+    }
+    #[test]
+    fn aligned_table() {
+        let rows = vec![
+            vec!["main.rs".to_string(), "12".to_string()],
+            vec!["a\\|b".to_string(), "1234".to_string()],
+        ];
+        assert_eq!(table(&[("File", false), ("Lines", true)], &rows), "\
+| File    | Lines |
+| ------- | ----: |
+| main.rs |    12 |
+| a\\|b    |  1234 |
+");
+    }
+}
@@ -0,0 +1,130 @@
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+//how many columns apart tab stops are, like wc and most terminals use
+const TAB_WIDTH: usize = 8;
+
+/**
+ * which counts to take besides lines, like wc does
+ */
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Measures {
+    pub bytes: bool,
+    pub chars: bool, //Unicode scalar values
+    pub graphemes: bool, //what a reader would call a character, like an emoji made of several scalar values
+    pub words: bool, //runs of characters separated by whitespace
+    pub max_line_length: bool, //the longest line, in both bytes and display columns
+}
+impl Measures {
+    /**
+     * checks if any of the counts need the decoded text, rather than just the bytes of a file
+     */
+    pub fn need_text(&self) -> bool {
+        return self.chars || self.graphemes || self.words || self.max_line_length;
+    }
+}
+
+/**
+ * the counts taken of some text, only the ones that were asked for are Some
+ */
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Measurements {
+    pub bytes: Option<usize>,
+    pub chars: Option<usize>,
+    pub graphemes: Option<usize>,
+    pub words: Option<usize>,
+    pub max_line_bytes: Option<usize>, //not counting the line ending
+    pub max_line_width: Option<usize>, //in display columns, with wide characters taking two and tabs going to the next tab stop
+}
+
+/**
+ * takes every count measures asks for from text, in one pass over it
+ * byte_count is how big the file text came from is, since the text could be in a different encoding than the file
+ */
+pub fn measure(text: &str, byte_count: usize, measures: &Measures) -> Measurements {
+    let mut measurements = Measurements { bytes: measures.bytes.then_some(byte_count), ..Measurements::default() };
+    if !measures.need_text() {
+        return measurements;
+    }
+
+    let (mut chars, mut graphemes, mut words, mut max_line_bytes, mut max_line_width) = (0, 0, 0, 0, 0);
+    //'\n' is whitespace, and never part of a bigger grapheme, so nothing counted here spans two lines
+    for line in text.split_inclusive('\n') {
+        if measures.chars {
+            chars += line.chars().count();
+        }
+        if measures.graphemes {
+            graphemes += line.graphemes(true).count();
+        }
+        if measures.words {
+            words += line.split_whitespace().count();
+        }
+        if measures.max_line_length {
+            let content = line.strip_suffix('\n').unwrap_or(line);
+            let content = content.strip_suffix('\r').unwrap_or(content);
+            max_line_bytes = max_line_bytes.max(content.len());
+            max_line_width = max_line_width.max(display_width(content));
+        }
+    }
+
+    measurements.chars = measures.chars.then_some(chars);
+    measurements.graphemes = measures.graphemes.then_some(graphemes);
+    measurements.words = measures.words.then_some(words);
+    measurements.max_line_bytes = measures.max_line_length.then_some(max_line_bytes);
+    measurements.max_line_width = measures.max_line_length.then_some(max_line_width);
+    return measurements;
+}
+
+/**
+ * how many columns a line takes up on a terminal
+ */
+pub fn display_width(line: &str) -> usize {
+    let mut width = 0;
+    for (i, part) in line.split('\t').enumerate() {
+        if i > 0 {
+            width = (width / TAB_WIDTH + 1) * TAB_WIDTH; //a tab goes to the next tab stop
+        }
+        width += part.width();
+    }
+    return width;
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EVERYTHING: Measures = Measures { bytes: true, chars: true, graphemes: true, words: true, max_line_length: true };
+
+    #[test]
+    fn measure_ascii() {
+        let text = "hello world\none  two\tthree\n\n";
+        assert_eq!(measure(text, text.len(), &EVERYTHING), Measurements {
+            bytes: Some(28),
+            chars: Some(28),
+            graphemes: Some(28),
+            words: Some(5),
+            max_line_bytes: Some(14),
+            max_line_width: Some(21), //"one  two" is 8 columns, so the tab goes to 16
+        });
+    }
+    #[test]
+    fn measure_unicode() {
+        //an accent made of two scalar values, a family emoji joined from five, and wide CJK characters
+        let text = "cafe\u{301} \u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}\r\n\u{4E2D}\u{6587}";
+        let measurements = measure(text, 12345, &EVERYTHING);
+        assert_eq!(measurements.bytes, Some(12345));
+        assert_eq!(measurements.chars, Some(15));
+        assert_eq!(measurements.graphemes, Some(9)); //"\r\n" is one grapheme
+        assert_eq!(measurements.words, Some(3));
+        assert_eq!(measurements.max_line_bytes, Some(25));
+        assert_eq!(measurements.max_line_width, Some(7)); //the family is one emoji, two columns wide
+    }
+    #[test]
+    fn only_measure_whats_asked_for() {
+        let measurements = measure("a b c", 5, &Measures { words: true, ..Measures::default() });
+        assert_eq!(measurements, Measurements { words: Some(3), ..Measurements::default() });
+        assert_eq!(measure("", 0, &EVERYTHING).max_line_width, Some(0));
+    }
+}
@@ -0,0 +1,92 @@
+use std::{
+    collections::BTreeMap,
+    num::NonZeroUsize,
+    sync::{mpsc, Mutex},
+    thread,
+};
+
+/**
+ * how many threads to use if the user doesn't say, one for every CPU
+ */
+pub fn default_jobs() -> usize {
+    return thread::available_parallelism().map_or(1, NonZeroUsize::get);
+}
+
+/**
+ * runs work on every item from items across jobs threads, and passes what it returns to output on the calling thread,
+ * in the same order the items came in, no matter which thread finished first
+ * output is called as soon as the next result in order is ready, so it doesn't have to wait for everything to finish
+ */
+pub fn map_in_order<T, R, I, W, O>(items: I, jobs: usize, work: W, mut output: O)
+where
+    T: Send,
+    R: Send,
+    I: Iterator<Item = T> + Send,
+    W: Fn(T) -> R + Sync,
+    O: FnMut(R),
+{
+    //nothing to gain from spawning a single thread
+    if jobs <= 1 {
+        items.map(work).for_each(output);
+        return;
+    }
+
+    let items = Mutex::new(items.enumerate());
+    let (sender, receiver) = mpsc::channel();
+    thread::scope(|scope| {
+        for _ in 0..jobs {
+            let sender = sender.clone();
+            let (items, work) = (&items, &work);
+            scope.spawn(move || loop {
+                //only hold the lock long enough to take the next item
+                let next = items.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).next();
+                let (index, item) = match next {
+                    Some(n) => n,
+                    None => return,
+                };
+                if sender.send((index, work(item))).is_err() {
+                    return;
+                }
+            });
+        }
+        drop(sender); //so the loop below ends once every worker is done
+
+        //results that finished before the ones in front of them, waiting their turn
+        let mut waiting: BTreeMap<usize, R> = BTreeMap::new();
+        let mut next_index = 0;
+        for (index, result) in receiver {
+            waiting.insert(index, result);
+            while let Some(result) = waiting.remove(&next_index) {
+                output(result);
+                next_index += 1;
+            }
+        }
+    });
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn results_stay_in_order() {
+        for jobs in [1, 2, 8] {
+            let mut results = Vec::new();
+            //make earlier items slower, so they'd finish last if order wasn't kept
+            map_in_order(0..50u64, jobs, |n| {
+                thread::sleep(Duration::from_micros((50 - n) * 20));
+                return n * 2;
+            }, |r| results.push(r));
+            assert_eq!(results, (0..50).map(|n| n * 2).collect::<Vec<u64>>(), "jobs {}", jobs);
+        }
+    }
+    #[test]
+    fn no_items() {
+        let mut called = false;
+        map_in_order(std::iter::empty::<u8>(), 4, |n| n, |_| called = true);
+        assert!(!called);
+    }
+}
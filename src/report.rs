@@ -0,0 +1,256 @@
+use std::{collections::BTreeMap, path::Path, time::Duration};
+
+use crate::{
+    delimited::{DelimitedReport, Separator},
+    html::HtmlReport,
+    json::{JsonReport, NdjsonReport},
+    markdown::MarkdownReport,
+    treemap::TreemapReport,
+    lengths::LengthDistribution,
+    measure::Measurements,
+    Config, FileCount, LineStats, FORMAT,
+};
+
+/**
+ * everything counted across all the files of a run
+ */
+#[derive(Debug, Default)]
+pub struct Totals {
+    pub files: usize,
+    pub lines: usize,
+    pub size: usize, //in bytes
+    pub line_stats: Option<LineStats>, //only if lines were classified
+    pub measurements: Measurements, //the sum of each count measured, and the longest of the longest lines
+    pub languages: BTreeMap<&'static str, (usize, usize)>, //language name -> (files, lines)
+    pub lengths: LengthDistribution,
+    pub long_lines: usize,
+    pub failed_files: usize, //files that couldn't be counted
+    pub skipped_binary_files: usize,
+    pub skipped_special_files: usize, //like named pipes and sockets
+    pub traversal_errors: usize,
+    pub failed_audits: usize,
+    pub elapsed: Duration, //how long the run took, set just before it's reported
+}
+impl Totals {
+    /**
+     * adds everything counted about one file
+     */
+    pub fn add(&mut self, count: &FileCount) {
+        self.files += 1;
+        self.lines += count.lines;
+        self.size += count.size;
+        if let Some(stats) = count.line_stats {
+            *self.line_stats.get_or_insert_with(LineStats::default) += stats;
+        }
+        let (total, measured) = (&mut self.measurements, &count.measurements);
+        let sum = |total: Option<usize>, measured: Option<usize>| measured.map(|n| total.unwrap_or(0) + n).or(total);
+        let max = |total: Option<usize>, measured: Option<usize>| measured.map(|n| total.unwrap_or(0).max(n)).or(total);
+        total.bytes = sum(total.bytes, measured.bytes);
+        total.chars = sum(total.chars, measured.chars);
+        total.graphemes = sum(total.graphemes, measured.graphemes);
+        total.words = sum(total.words, measured.words);
+        total.max_line_bytes = max(total.max_line_bytes, measured.max_line_bytes);
+        total.max_line_width = max(total.max_line_width, measured.max_line_width);
+        let language = self.languages.entry(count.language.map_or("Unknown", |l| l.name)).or_default();
+        language.0 += 1;
+        language.1 += count.lines;
+        if let Some(line_lengths) = &count.line_lengths {
+            self.lengths.merge(&line_lengths.distribution);
+            self.long_lines += line_lengths.long_lines.len();
+        }
+    }
+
+    /**
+     * how many files weren't counted on purpose
+     */
+    pub fn skipped_files(&self) -> usize {
+        return self.skipped_binary_files + self.skipped_special_files;
+    }
+
+    /**
+     * the totals in a sentence, like "12 files, 3456 lines, 1 skipped, 0 failed, in 0.05s"
+     */
+    pub fn summary(&self) -> String {
+        return format!("{} files, {} lines, {} skipped, {} failed, in {:.2}s",
+            self.files, self.lines, self.skipped_files(), self.failed_files, self.elapsed.as_secs_f64());
+    }
+}
+
+/**
+ * checks if the human readable formats should print the summary
+ * it's left out when counting a single file, unless it's all that was asked for
+ */
+pub fn show_summary(config: &Config) -> bool {
+    return config.summary_only || (!config.no_summary && config.path_is_directory);
+}
+
+/**
+ * a way to print the results of a run, picked by config.output_format
+ * files are passed in the order they were found, problems are already printed to std. error before they're passed in
+ * with config.summary_only, no files are passed in, only the totals
+ */
+pub trait Report {
+    /**
+     * a file was counted
+     */
+    fn file(&mut self, path: &str, count: &FileCount);
+
+    /**
+     * a file couldn't be counted
+     */
+    fn failed(&mut self, _path: &str, _message: &str) {}
+
+    /**
+     * a file was skipped because it looks binary
+     */
+    fn binary(&mut self, _path: &str) {}
+
+    /**
+     * something went wrong while searching, or a special file was skipped
+     */
+    fn warning(&mut self, _path: &Path, _message: &str) {}
+
+    /**
+     * everything has been counted
+     */
+    fn finish(&mut self, totals: &Totals);
+}
+
+/**
+ * makes the report config.output_format asks for
+ */
+pub fn new_report(config: &Config) -> Box<dyn Report + '_> {
+    match config.output_format {
+        FORMAT::Markdown => return Box::new(MarkdownReport::new(config)),
+        FORMAT::Html => return Box::new(HtmlReport::new(config)),
+        FORMAT::SvgTreemap => return Box::new(TreemapReport::new(config)),
+        FORMAT::Json => return Box::new(JsonReport::new(config)),
+        FORMAT::Ndjson => return Box::new(NdjsonReport::new(config)),
+        FORMAT::Csv => return Box::new(DelimitedReport::new(config, Separator::Comma)),
+        FORMAT::Tsv => return Box::new(DelimitedReport::new(config, Separator::Tab)),
+        _ => return Box::new(TextReport { config, i: 1 }),
+    }
+}
+
+//the plain text formats: Default, Bullet and Numeric
+struct TextReport<'a> {
+    config: &'a Config,
+    i: usize, //counter variable for the Numberic format
+}
+impl Report for TextReport<'_> {
+    fn file(&mut self, path: &str, count: &FileCount) {
+        //a single file is printed on its own, without a list around it
+        if self.config.path_is_directory {
+            //format output as specified by config.output_format
+            match self.config.output_format {
+                FORMAT::Bullet => print!("*\t"),
+                FORMAT::Numeric => print!("{}.)\t", self.i),
+                _ => print!("\t"),
+            }
+        }
+        print_file_count(path, count, self.config);
+        self.i += 1;
+    }
+
+    fn finish(&mut self, totals: &Totals) {
+        let config = self.config;
+        if !config.path_is_directory {
+            if show_summary(config) {
+                println!("Total: {}", totals.summary());
+            }
+            return;
+        }
+
+        if totals.skipped_binary_files > 0 {
+            println!("Skipped binary files: {}", totals.skipped_binary_files);
+        }
+
+        if config.group_by_language {
+            println!("Lines by language:");
+            for (name, (files, lines)) in &totals.languages {
+                println!("\t{}: {} Files, {} Lines", name, files, lines);
+            }
+        }
+
+        if config.line_stats {
+            println!("Line lengths: {}", totals.lengths);
+        }
+        if let Some(limit) = config.long_line_limit {
+            println!("Lines longer than {} columns: {}", limit, totals.long_lines);
+        }
+
+        if show_summary(config) {
+            println!("Total: {}", totals.summary());
+        }
+    }
+}
+
+//prints the line count of a file, and anything else config asks for
+fn print_file_count(path_name: &str, count: &FileCount, config: &Config) {
+    print!("{}: {} Lines", path_name, count.lines);
+    //the rest of the columns, in the same order wc uses
+    let measurements = &count.measurements;
+    if let Some(words) = measurements.words {
+        print!(", {} Words", words);
+    }
+    if let Some(chars) = measurements.chars {
+        print!(", {} Chars", chars);
+    }
+    if let Some(graphemes) = measurements.graphemes {
+        print!(", {} Graphemes", graphemes);
+    }
+    if let Some(bytes) = measurements.bytes {
+        print!(", {} Bytes", bytes);
+    }
+    if let (Some(max_bytes), Some(max_width)) = (measurements.max_line_bytes, measurements.max_line_width) {
+        print!(", {} Max Line Bytes, {} Max Line Columns", max_bytes, max_width);
+    }
+    if let Some(stats) = count.line_stats {
+        print!(" ({} code, {} comments, {} blank)", stats.code, stats.comment, stats.blank);
+    }
+    if config.show_encoding {
+        print!(" [{}]", count.encoding);
+    }
+    println!();
+    if let Some(hygiene) = &count.hygiene {
+        println!("\t\t{}", hygiene);
+    }
+    if let Some(line_lengths) = &count.line_lengths {
+        if config.line_stats {
+            println!("\t\tLine lengths: {}", line_lengths.distribution);
+        }
+        for long_line in &line_lengths.long_lines {
+            println!("\t\t{}:{}: {} Columns", path_name, long_line.line_number, long_line.columns);
+        }
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summary_sentence() {
+        let totals = Totals {
+            files: 12,
+            lines: 3456,
+            skipped_binary_files: 2,
+            skipped_special_files: 1,
+            failed_files: 1,
+            elapsed: Duration::from_millis(50),
+            ..Totals::default()
+        };
+        assert_eq!(totals.skipped_files(), 3);
+        assert_eq!(totals.summary(), "12 files, 3456 lines, 3 skipped, 1 failed, in 0.05s");
+    }
+    #[test]
+    fn when_to_show_summary() {
+        let directory = Config { path_is_directory: true, ..Config::default() };
+        assert!(show_summary(&directory));
+        assert!(!show_summary(&Config { no_summary: true, ..directory }));
+        assert!(!show_summary(&Config::default())); //a single file
+        assert!(show_summary(&Config { summary_only: true, ..Config::default() }));
+    }
+}
@@ -0,0 +1,271 @@
+use std::{
+    collections::BTreeMap,
+    path::{Component, Path},
+};
+
+use crate::{
+    html::escape_html,
+    report::{Report, Totals},
+    Config, FileCount,
+};
+
+const WIDTH: f64 = 1200.0;
+const HEIGHT: f64 = 800.0;
+//room for the name of a directory above its contents
+const LABEL_HEIGHT: f64 = 14.0;
+//the space between a directory's outline and its contents
+const PADDING: f64 = 2.0;
+//roughly how wide a character of a label is, to tell if it fits
+const CHAR_WIDTH: f64 = 7.0;
+//how tall each row of the legend is, and how many languages go in a row
+const LEGEND_ROW_HEIGHT: f64 = 20.0;
+const LEGEND_COLUMNS: usize = 6;
+//the colors of languages, the ones with the most lines first
+const PALETTE: [&str; 12] = [
+    "#4e79a7", "#f28e2b", "#e15759", "#76b7b2", "#59a14f", "#edc948",
+    "#b07aa1", "#ff9da7", "#9c755f", "#bab0ac", "#86bcb6", "#d37295",
+];
+
+/**
+ * a rectangle, in the coordinates of the image
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rect {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+/**
+ * splits rect into one rectangle for each of areas, each as close to a square as it can be, using the squarified algorithm
+ * areas should be sorted biggest first, and are scaled to fill rect
+ */
+pub fn squarify(areas: &[f64], rect: Rect) -> Vec<Rect> {
+    let total: f64 = areas.iter().sum();
+    if total <= 0.0 {
+        return areas.iter().map(|_| Rect { width: 0.0, height: 0.0, ..rect }).collect();
+    }
+    let scale = rect.width * rect.height / total;
+    let areas: Vec<f64> = areas.iter().map(|area| area * scale).collect();
+
+    //the worst aspect ratio of a row of areas laid along a side of length side
+    let worst = |row: &[f64], side: f64| -> f64 {
+        let sum: f64 = row.iter().sum();
+        let (min, max) = row.iter().fold((f64::INFINITY, 0.0f64), |(min, max), &a| (min.min(a), max.max(a)));
+        return (side * side * max / (sum * sum)).max(sum * sum / (side * side * min));
+    };
+
+    let mut rects = Vec::with_capacity(areas.len());
+    let mut free = rect;
+    let mut start = 0;
+    while start < areas.len() {
+        let side = free.width.min(free.height);
+        //keep adding areas to the row while that makes it more square
+        let mut end = start + 1;
+        while end < areas.len() && worst(&areas[start..=end], side) <= worst(&areas[start..end], side) {
+            end += 1;
+        }
+        let row = &areas[start..end];
+        let thickness = if side > 0.0 { row.iter().sum::<f64>() / side } else { 0.0 };
+        //the row goes along the shorter side of what's left
+        let mut offset = 0.0;
+        for area in row {
+            let length = if thickness > 0.0 { area / thickness } else { 0.0 };
+            if free.width >= free.height {
+                rects.push(Rect { x: free.x, y: free.y + offset, width: thickness, height: length });
+            } else {
+                rects.push(Rect { x: free.x + offset, y: free.y, width: length, height: thickness });
+            }
+            offset += length;
+        }
+        if free.width >= free.height {
+            free.x += thickness;
+            free.width = (free.width - thickness).max(0.0);
+        } else {
+            free.y += thickness;
+            free.height = (free.height - thickness).max(0.0);
+        }
+        start = end;
+    }
+    return rects;
+}
+
+//a file or directory of the tree that's drawn
+#[derive(Debug, Default)]
+struct Node {
+    lines: usize, //of the file, or every file under the directory
+    language: Option<&'static str>, //only files have one
+    children: BTreeMap<String, Node>, //only directories have any
+}
+impl Node {
+    //adds a file, at the path made of names
+    fn insert(&mut self, names: &[String], lines: usize, language: &'static str) {
+        self.lines += lines;
+        match names {
+            [] => self.language = Some(language),
+            [name, rest @ ..] => self.children.entry(name.clone()).or_default().insert(rest, lines, language),
+        }
+    }
+}
+
+/**
+ * --format=SVG-TREEMAP, an image of every directory and file, with the area of each proportional to its lines,
+ * and files colored by their language, with a legend and the summary under it
+ */
+pub struct TreemapReport<'a> {
+    config: &'a Config,
+    root: Node,
+}
+impl TreemapReport<'_> {
+    pub fn new(config: &Config) -> TreemapReport<'_> {
+        return TreemapReport { config, root: Node::default() };
+    }
+}
+impl Report for TreemapReport<'_> {
+    fn file(&mut self, path: &str, count: &FileCount) {
+        //the tree starts at the path that was searched
+        let relative = Path::new(path).strip_prefix(&self.config.path).unwrap_or(Path::new(path));
+        let mut names: Vec<String> = relative.components().filter_map(|component| match component {
+            Component::Normal(name) => Some(name.to_string_lossy().into_owned()),
+            _ => None,
+        }).collect();
+        if names.is_empty() {
+            //a single file was counted
+            names.push(path.to_string());
+        }
+        self.root.insert(&names, count.lines, count.language.map_or("Unknown", |l| l.name));
+    }
+
+    fn finish(&mut self, totals: &Totals) {
+        //the languages with the most lines get the first colors
+        let mut languages: Vec<(&str, usize)> = totals.languages.iter().map(|(name, (_, lines))| (*name, *lines)).collect();
+        languages.sort_by_key(|(_, lines)| std::cmp::Reverse(*lines));
+        let colors: BTreeMap<&str, &str> = languages.iter().enumerate().map(|(i, (name, _))| (*name, PALETTE[i % PALETTE.len()])).collect();
+
+        let legend_rows = languages.len().div_ceil(LEGEND_COLUMNS);
+        let summary_rows = if self.config.no_summary { 0 } else { 1 };
+        let height = HEIGHT + (legend_rows + summary_rows) as f64 * LEGEND_ROW_HEIGHT + PADDING * 2.0;
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\" font-family=\"sans-serif\" font-size=\"11\">\n<title>{}</title>\n",
+            WIDTH, height, WIDTH, height, escape_html(&format!("Lines of {}", self.config.path))
+        );
+        let name = if self.config.path_is_directory { self.config.path.as_str() } else { "" };
+        draw(&mut svg, name, name, &self.root, Rect { x: 0.0, y: 0.0, width: WIDTH, height: HEIGHT }, &colors);
+
+        //which color is which language
+        let column_width = WIDTH / LEGEND_COLUMNS as f64;
+        for (i, (language, lines)) in languages.iter().enumerate() {
+            let x = (i % LEGEND_COLUMNS) as f64 * column_width + PADDING;
+            let y = HEIGHT + PADDING * 2.0 + (i / LEGEND_COLUMNS) as f64 * LEGEND_ROW_HEIGHT;
+            svg += &format!(
+                "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"12\" height=\"12\" fill=\"{}\"/><text x=\"{:.1}\" y=\"{:.1}\">{} ({} lines)</text>\n",
+                x, y, colors[language], x + 16.0, y + 10.0, escape_html(language), lines
+            );
+        }
+        if !self.config.no_summary {
+            let y = HEIGHT + PADDING * 2.0 + legend_rows as f64 * LEGEND_ROW_HEIGHT + 10.0;
+            svg += &format!("<text x=\"{:.1}\" y=\"{:.1}\">Total: {}</text>\n", PADDING, y, escape_html(&totals.summary()));
+        }
+        svg += "</svg>";
+        println!("{}", svg);
+    }
+}
+
+//draws node and everything under it into rect
+fn draw(svg: &mut String, name: &str, path: &str, node: &Node, rect: Rect, colors: &BTreeMap<&str, &str>) {
+    if rect.width < 1.0 || rect.height < 1.0 {
+        return;
+    }
+    let title = format!("{}: {} lines", path, node.lines);
+    if let Some(language) = node.language {
+        *svg += &format!(
+            "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" fill=\"{}\" stroke=\"#fff\" stroke-width=\"0.5\"><title>{} ({})</title></rect>\n",
+            rect.x, rect.y, rect.width, rect.height, colors.get(language).unwrap_or(&"#999"), escape_html(&title), escape_html(language)
+        );
+        label(svg, name, rect, rect.y + LABEL_HEIGHT - 3.0);
+        return;
+    }
+
+    *svg += &format!(
+        "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" fill=\"#eee\" stroke=\"#666\" stroke-width=\"0.5\"><title>{}</title></rect>\n",
+        rect.x, rect.y, rect.width, rect.height, escape_html(&title)
+    );
+    label(svg, name, rect, rect.y + LABEL_HEIGHT - 3.0);
+    let inner = Rect {
+        x: rect.x + PADDING,
+        y: rect.y + LABEL_HEIGHT,
+        width: rect.width - 2.0 * PADDING,
+        height: rect.height - LABEL_HEIGHT - PADDING,
+    };
+    if inner.width < 1.0 || inner.height < 1.0 {
+        return;
+    }
+
+    let mut children: Vec<(&String, &Node)> = node.children.iter().filter(|(_, child)| child.lines > 0).collect();
+    children.sort_by_key(|(_, child)| std::cmp::Reverse(child.lines));
+    let areas: Vec<f64> = children.iter().map(|(_, child)| child.lines as f64).collect();
+    for ((child_name, child), child_rect) in children.iter().zip(squarify(&areas, inner)) {
+        let child_path = if path.is_empty() { child_name.to_string() } else { format!("{}/{}", path, child_name) };
+        draw(svg, child_name, &child_path, child, child_rect, colors);
+    }
+}
+
+//writes name at the top left of rect, if there's room for it, cut short if it's too long
+fn label(svg: &mut String, name: &str, rect: Rect, baseline: f64) {
+    if name.is_empty() || rect.height < LABEL_HEIGHT || rect.width < CHAR_WIDTH * 3.0 {
+        return;
+    }
+    let fits = ((rect.width - 2.0 * PADDING) / CHAR_WIDTH) as usize;
+    let text: String = if name.chars().count() > fits {
+        name.chars().take(fits.saturating_sub(1)).chain(['…']).collect()
+    } else {
+        name.to_string()
+    };
+    *svg += &format!("<text x=\"{:.1}\" y=\"{:.1}\">{}</text>\n", rect.x + PADDING, baseline, escape_html(&text));
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AREA: Rect = Rect { x: 0.0, y: 0.0, width: 6.0, height: 4.0 };
+
+    #[test]
+    fn squarified_layout() {
+        //the example from the paper the algorithm comes from
+        let rects = squarify(&[6.0, 6.0, 4.0, 3.0, 2.0, 2.0, 1.0], AREA);
+        assert_eq!(rects.len(), 7);
+        assert_eq!(rects[0], Rect { x: 0.0, y: 0.0, width: 3.0, height: 2.0 });
+        assert_eq!(rects[1], Rect { x: 0.0, y: 2.0, width: 3.0, height: 2.0 });
+        //every rectangle has the area it was given, and they fill the whole area
+        let areas: Vec<f64> = rects.iter().map(|r| r.width * r.height).collect();
+        for (area, expected) in areas.iter().zip([6.0, 6.0, 4.0, 3.0, 2.0, 2.0, 1.0]) {
+            assert!((area - expected).abs() < 1e-9);
+        }
+        for rect in &rects {
+            assert!(rect.x >= 0.0 && rect.y >= 0.0 && rect.x + rect.width <= 6.0 + 1e-9 && rect.y + rect.height <= 4.0 + 1e-9);
+        }
+    }
+    #[test]
+    fn squarify_scales_to_fit() {
+        let rects = squarify(&[300.0, 100.0], AREA);
+        assert_eq!(rects[0].width * rects[0].height, 18.0);
+        assert_eq!(rects[1].width * rects[1].height, 6.0);
+        //nothing to draw
+        assert!(squarify(&[0.0], AREA).iter().all(|r| r.width == 0.0));
+    }
+    #[test]
+    fn build_tree() {
+        let mut root = Node::default();
+        root.insert(&["src".to_string(), "main.rs".to_string()], 10, "Rust");
+        root.insert(&["src".to_string(), "lib.rs".to_string()], 30, "Rust");
+        root.insert(&["README.md".to_string()], 5, "Markdown");
+        assert_eq!(root.lines, 45);
+        assert_eq!(root.children["src"].lines, 40);
+        assert_eq!(root.children["src"].children["lib.rs"].language, Some("Rust"));
+        assert_eq!(root.children["src"].language, None);
+    }
+}
@@ -0,0 +1,755 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt,
+    fs::{self, FileType, Metadata},
+    io,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        mpsc, Arc, Condvar, Mutex,
+    },
+    thread::{self, JoinHandle},
+    vec,
+};
+
+use crate::{glob::PathPattern, ignore::IgnoreRules};
+
+/**
+ * what to do with symbolic links found during a walk
+ */
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SymlinkPolicy {
+    Never, //skip every symlink
+    #[default]
+    Follow, //follow every symlink
+    WithinRoot, //only follow symlinks that point somewhere inside the path being searched
+}
+
+/**
+ * options that control which files a walk finds
+ */
+#[derive(Debug, Default, Clone)]
+pub struct WalkOptions {
+    pub recursive: bool, //search through subdirectories
+    pub honor_ignore_files: bool, //skip anything matched by .gitignore, .ignore, .git/info/exclude, or core.excludesFile
+    pub excludes: Vec<PathPattern>, //skip files and whole directories matching any of these
+    pub includes: Vec<PathPattern>, //if not empty, only list files matching one of these, or in a directory that does
+    pub symlinks: SymlinkPolicy,
+    pub jobs: usize, //how many threads search directories at once
+}
+
+/**
+ * what the walker was trying to do when something went wrong
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operation {
+    OpenDirectory,
+    ReadEntry,
+    GetMetadata,
+    FollowSymlink,
+}
+impl fmt::Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Operation::OpenDirectory => write!(f, "open directory"),
+            Operation::ReadEntry => write!(f, "read an entry of directory"),
+            Operation::GetMetadata => write!(f, "get metadata of"),
+            Operation::FollowSymlink => write!(f, "follow symlink"),
+        }
+    }
+}
+
+/**
+ * what went wrong during a walk
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WarningKind {
+    Io(io::ErrorKind),
+    SymlinkCycle, //a symlink points back to a directory that's already being searched
+    AlreadySearched, //a directory was reached a second time, through another symlink
+}
+impl fmt::Display for WarningKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WarningKind::Io(kind) => write!(f, "{}", kind),
+            WarningKind::SymlinkCycle => write!(f, "it leads back to a directory that contains it"),
+            WarningKind::AlreadySearched => write!(f, "it leads to a directory that was already searched"),
+        }
+    }
+}
+
+/**
+ * an error that happened during a walk, the walk skips whatever caused it and keeps going
+ */
+#[derive(Debug)]
+pub struct TraversalWarning {
+    pub path: PathBuf,
+    pub operation: Operation,
+    pub kind: WarningKind,
+}
+impl fmt::Display for TraversalWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Could not {} {}: {}", self.operation, self.path.display(), self.kind)
+    }
+}
+
+/**
+ * kinds of files that aren't regular files or directories, reading them could block forever or never end
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpecialKind {
+    NamedPipe,
+    Socket,
+    BlockDevice,
+    CharacterDevice,
+    Other,
+}
+impl fmt::Display for SpecialKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SpecialKind::NamedPipe => write!(f, "named pipe"),
+            SpecialKind::Socket => write!(f, "socket"),
+            SpecialKind::BlockDevice => write!(f, "block device"),
+            SpecialKind::CharacterDevice => write!(f, "character device"),
+            SpecialKind::Other => write!(f, "special file"),
+        }
+    }
+}
+
+/**
+ * returns what kind of special file something is, or None if it's a regular file, directory, or symlink
+ */
+pub fn special_kind(file_type: &FileType) -> Option<SpecialKind> {
+    if file_type.is_file() || file_type.is_dir() || file_type.is_symlink() {
+        return None;
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::FileTypeExt;
+        if file_type.is_fifo() {
+            return Some(SpecialKind::NamedPipe);
+        } else if file_type.is_socket() {
+            return Some(SpecialKind::Socket);
+        } else if file_type.is_block_device() {
+            return Some(SpecialKind::BlockDevice);
+        } else if file_type.is_char_device() {
+            return Some(SpecialKind::CharacterDevice);
+        }
+    }
+    return Some(SpecialKind::Other);
+}
+
+/**
+ * a special file the walk found, but didn't list
+ */
+#[derive(Debug, PartialEq)]
+pub struct SkippedFile {
+    pub path: PathBuf,
+    pub kind: SpecialKind,
+}
+impl fmt::Display for SkippedFile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Skipped {} {}", self.kind, self.path.display())
+    }
+}
+
+/**
+ * something a walk found
+ */
+#[derive(Debug)]
+pub enum WalkEntry {
+    File(PathBuf),
+    Skipped(SkippedFile),
+    Warning(TraversalWarning),
+}
+
+//uniquely identifies a directory, so the same one isn't searched twice
+#[cfg(unix)]
+type DirectoryId = (u64, u64); //(device, inode)
+#[cfg(not(unix))]
+type DirectoryId = PathBuf; //canonical path
+
+#[cfg(unix)]
+fn directory_id(_path: &Path, metadata: &Metadata) -> Option<DirectoryId> {
+    use std::os::unix::fs::MetadataExt;
+    return Some((metadata.dev(), metadata.ino()));
+}
+#[cfg(not(unix))]
+fn directory_id(path: &Path, _metadata: &Metadata) -> Option<DirectoryId> {
+    return fs::canonicalize(path).ok();
+}
+
+/**
+ * starts searching path on options.jobs threads, and returns everything found as soon as it's found
+ * entries always come out in the same order a single threaded depth first search would find them,
+ * with the entries of each directory sorted by name, no matter which thread finishes first
+ * a directory reachable through more than one symlink is only searched once, through whichever path comes first in that order,
+ * and the other ways to it are warnings
+ */
+#[allow(clippy::clone_on_copy)] //DirectoryId is only Copy on unix
+pub fn walk_files(path: &Path, options: WalkOptions) -> WalkFiles {
+    let threads = options.jobs.max(1);
+    let shared = Arc::new(Shared {
+        ignore_rules: options.honor_ignore_files.then(|| IgnoreRules::for_root(path)),
+        canonical_root: fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf()),
+        options,
+        queues: (0..threads).map(|_| Mutex::new(VecDeque::new())).collect(),
+        pending: AtomicUsize::new(0),
+        visited: Mutex::new(HashMap::new()),
+        idle: Mutex::new(()),
+        work_available: Condvar::new(),
+        cancelled: AtomicBool::new(false),
+    });
+
+    //the root itself is always searched, even if it's a symlink
+    let root = match fs::metadata(path) {
+        Ok(metadata) => {
+            let (sender, listing) = mpsc::channel();
+            let id = directory_id(path, &metadata);
+            if let Some(id) = &id {
+                shared.visited.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).insert(id.clone(), path.to_path_buf());
+            }
+            shared.push_job(0, Job {
+                path: path.to_path_buf(),
+                relative_path: String::new(),
+                id: id.clone(),
+                ancestors: Vec::new(),
+                ignore_rules: shared.ignore_rules.clone(),
+                included: shared.options.includes.is_empty(),
+                sender,
+            });
+            Listed::Directory { path: path.to_path_buf(), id, listing }
+        },
+        Err(e) => Listed::Entry(WalkEntry::Warning(TraversalWarning { path: path.to_path_buf(), operation: Operation::GetMetadata, kind: WarningKind::Io(e.kind()) })),
+    };
+    let workers = (0..threads).map(|worker| {
+        let shared = Arc::clone(&shared);
+        thread::spawn(move || shared.run_worker(worker))
+    }).collect();
+
+    return WalkFiles { frames: vec![vec![root].into_iter()], shared, workers };
+}
+
+//what the listing of a directory holds, subdirectories are listed separately, possibly on other threads
+enum Listed {
+    Entry(WalkEntry),
+    Directory { path: PathBuf, id: Option<DirectoryId>, listing: mpsc::Receiver<Vec<Listed>> },
+}
+
+/**
+ * the entries of a walk, in depth first order, see walk_files
+ * waits for directories to be listed as it reaches them, stops the walk if dropped early
+ * dropping it waits for every thread of the walk to end, so none outlive it
+ */
+pub struct WalkFiles {
+    frames: Vec<vec::IntoIter<Listed>>, //the rest of the listing of every directory being searched
+    shared: Arc<Shared>,
+    workers: Vec<JoinHandle<()>>,
+}
+impl Iterator for WalkFiles {
+    type Item = WalkEntry;
+
+    fn next(&mut self) -> Option<WalkEntry> {
+        loop {
+            let next = self.frames.last_mut()?.next();
+            match next {
+                None => {self.frames.pop();},
+                Some(Listed::Entry(entry)) => return Some(entry),
+                Some(Listed::Directory { path, id, listing }) => {
+                    //every path before this one in walk order has been listed by now, so if one of them leads to the same directory,
+                    //it's been claimed by that path, and this one was queued for nothing
+                    if let Some(id) = &id {
+                        if self.shared.visited.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).get(id) != Some(&path) {
+                            return Some(WalkEntry::Warning(TraversalWarning { path, operation: Operation::FollowSymlink, kind: WarningKind::AlreadySearched }));
+                        }
+                    }
+                    //waits for a worker to list it, if one hasn't already
+                    if let Ok(entries) = listing.recv() {
+                        self.frames.push(entries.into_iter());
+                    }
+                },
+            }
+        }
+    }
+}
+impl Drop for WalkFiles {
+    fn drop(&mut self) {
+        self.shared.cancelled.store(true, Ordering::SeqCst);
+        self.shared.wake(true);
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+//a directory waiting to be listed
+struct Job {
+    path: PathBuf,
+    relative_path: String,
+    id: Option<DirectoryId>,
+    ancestors: Vec<DirectoryId>, //the directories containing this one
+    ignore_rules: Option<IgnoreRules>, //the rules from the directories containing this one
+    included: bool, //this directory, or one containing it, matches an include pattern, so every file in it is listed
+    sender: mpsc::Sender<Vec<Listed>>,
+}
+
+//state shared by every thread of a walk
+struct Shared {
+    options: WalkOptions,
+    ignore_rules: Option<IgnoreRules>, //the rules that apply to the root
+    canonical_root: PathBuf,
+    queues: Vec<Mutex<VecDeque<Job>>>, //one for each worker, which takes from the back of its own, and steals from the front of others
+    pending: AtomicUsize, //jobs that are queued or being worked on
+    //every directory queued so far, and the path it's searched through, so none is searched twice
+    //paths compare in the same order the walk puts them in, so the first in that order wins, no matter which thread got there first
+    visited: Mutex<HashMap<DirectoryId, PathBuf>>,
+    idle: Mutex<()>, //what idle workers wait on work_available with
+    work_available: Condvar,
+    cancelled: AtomicBool, //set once nobody wants the rest of the walk
+}
+impl Shared {
+    fn run_worker(&self, worker: usize) {
+        while !self.cancelled.load(Ordering::SeqCst) {
+            if let Some(job) = self.find_job(worker) {
+                self.walk_directory(worker, job);
+                if self.pending.fetch_sub(1, Ordering::SeqCst) == 1 {
+                    self.wake(true); //that was the last one, so everyone can stop
+                }
+                continue;
+            }
+            //another worker is still listing a directory, and might find more
+            //everything is checked again while holding idle, and wake takes it before notifying,
+            //so a job pushed or finished after the checks can't notify before this starts waiting
+            let idle = self.idle.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            if self.cancelled.load(Ordering::SeqCst) || self.pending.load(Ordering::SeqCst) == 0 {
+                return;
+            }
+            if self.queues.iter().all(|queue| queue.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).is_empty()) {
+                drop(self.work_available.wait(idle));
+            }
+        }
+    }
+
+    fn push_job(&self, worker: usize, job: Job) {
+        self.pending.fetch_add(1, Ordering::SeqCst);
+        self.queues[worker].lock().unwrap_or_else(|poisoned| poisoned.into_inner()).push_back(job);
+        self.wake(false);
+    }
+
+    //wakes one idle worker, or all of them
+    fn wake(&self, all: bool) {
+        let _idle = self.idle.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if all {
+            self.work_available.notify_all();
+        } else {
+            self.work_available.notify_one();
+        }
+    }
+
+    //takes the newest job from a worker's own queue, so it goes depth first, or the oldest one from another worker's queue
+    fn find_job(&self, worker: usize) -> Option<Job> {
+        let queue = |i: usize| self.queues[i].lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some(job) = queue(worker).pop_back() {
+            return Some(job);
+        }
+        return (1..self.queues.len()).find_map(|offset| queue((worker + offset) % self.queues.len()).pop_front());
+    }
+
+    //lists one directory, queueing its subdirectories to be listed too, and sends the listing to whoever is reading the walk
+    #[allow(clippy::clone_on_copy)] //DirectoryId is only Copy on unix
+    fn walk_directory(&self, worker: usize, job: Job) {
+        let Job { path, relative_path, id, mut ancestors, mut ignore_rules, included, sender } = job;
+        let mut listing = Vec::new();
+        let paths = match fs::read_dir(&path) {
+            Ok(paths) => paths,
+            Err(e) => {
+                listing.push(warning(&path, Operation::OpenDirectory, WarningKind::Io(e.kind())));
+                let _ = sender.send(listing);
+                return;
+            },
+        };
+        if let Some(rules) = ignore_rules.as_mut() {
+            rules.push_directory(&path, &relative_path);
+        }
+        ancestors.extend(id);
+
+        //sorted by name, so the order doesn't depend on the filesystem, or the order files were made in
+        let mut entries = Vec::new();
+        for path_result in paths {
+            match path_result {
+                Ok(entry) => entries.push(entry),
+                Err(e) => listing.push(warning(&path, Operation::ReadEntry, WarningKind::Io(e.kind()))),
+            }
+        }
+        entries.sort_by_key(|entry| entry.file_name());
+
+        for entry in entries {
+            let full_path = entry.path();
+            let file_type = entry.file_type().ok();
+            let is_symlink = file_type.is_some_and(|file_type| file_type.is_symlink());
+            if is_symlink && !self.should_follow(&full_path) {
+                continue;
+            }
+            let name = full_path.file_name().unwrap_or_default().to_string_lossy();
+            let relative_child = if relative_path.is_empty() { name.to_string() } else { format!("{}/{}", relative_path, name) };
+
+            //checked before anything else, so an excluded directory is never walked at all,
+            //and nothing is looked up about an ignored or excluded path, like a broken symlink
+            let skip = |is_dir: bool| {
+                //git never looks inside .git, so neither do we
+                let ignored = ignore_rules.as_ref().is_some_and(|rules| (is_dir && name == ".git") || rules.is_ignored(&relative_child, is_dir));
+                ignored || self.options.excludes.iter().any(|pattern| pattern.matches(&relative_child, is_dir))
+            };
+            match file_type {
+                Some(file_type) if !is_symlink => if skip(file_type.is_dir()) { continue; },
+                //what a symlink points to isn't known yet, so it's only skipped now if it would be either way
+                _ => if skip(false) && skip(true) { continue; },
+            }
+            //follows symlinks, so a link to a directory counts as a directory
+            let metadata = match fs::metadata(&full_path) {
+                Ok(metadata) => metadata,
+                Err(e) => {listing.push(warning(&full_path, Operation::GetMetadata, WarningKind::Io(e.kind()))); continue;},
+            };
+            let is_dir = metadata.is_dir();
+            if (is_symlink || file_type.is_none()) && skip(is_dir) {
+                continue;
+            }
+
+            if let Some(kind) = special_kind(&metadata.file_type()) {
+                listing.push(Listed::Entry(WalkEntry::Skipped(SkippedFile { path: full_path, kind })));
+            } else if !is_dir {
+                if included || self.options.includes.iter().any(|pattern| pattern.matches(&relative_child, false)) {
+                    listing.push(Listed::Entry(WalkEntry::File(full_path)));
+                }
+            } else if self.options.recursive {
+                let child_id = directory_id(&full_path, &metadata);
+                //checked before queueing, so a directory reachable many ways, like through a diamond of symlinks, is only listed once
+                if let Some(child_id) = &child_id {
+                    if ancestors.contains(child_id) {
+                        listing.push(warning(&full_path, Operation::FollowSymlink, WarningKind::SymlinkCycle));
+                        continue;
+                    }
+                    let mut visited = self.visited.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+                    if visited.get(child_id).is_some_and(|claimed| *claimed < full_path) {
+                        listing.push(warning(&full_path, Operation::FollowSymlink, WarningKind::AlreadySearched));
+                        continue;
+                    }
+                    //a path later in walk order may have claimed it first, that one turns into a warning when it's reached
+                    visited.insert(child_id.clone(), full_path.clone());
+                }
+                let child_included = included || self.options.includes.iter().any(|pattern| pattern.matches(&relative_child, true));
+                let (child_sender, child_listing) = mpsc::channel();
+                self.push_job(worker, Job {
+                    path: full_path.clone(),
+                    relative_path: relative_child,
+                    id: child_id.clone(),
+                    ancestors: ancestors.clone(),
+                    ignore_rules: ignore_rules.clone(),
+                    included: child_included,
+                    sender: child_sender,
+                });
+                listing.push(Listed::Directory { path: full_path, id: child_id, listing: child_listing });
+            }
+        }
+        let _ = sender.send(listing);
+    }
+
+    //checks if the symlink at path should be followed, according to the symlink policy
+    fn should_follow(&self, path: &Path) -> bool {
+        match self.options.symlinks {
+            SymlinkPolicy::Never => return false,
+            SymlinkPolicy::Follow => return true,
+            //broken links are followed, so they get reported
+            SymlinkPolicy::WithinRoot => return fs::canonicalize(path).map_or(true, |target| target.starts_with(&self.canonical_root)),
+        }
+    }
+}
+
+fn warning(path: &Path, operation: Operation, kind: WarningKind) -> Listed {
+    return Listed::Entry(WalkEntry::Warning(TraversalWarning { path: path.to_path_buf(), operation, kind }));
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //collects everything a walk finds
+    #[derive(Debug, Default)]
+    struct Walk {
+        files: Vec<PathBuf>,
+        warnings: Vec<TraversalWarning>,
+        skipped: Vec<SkippedFile>,
+    }
+    fn list_files(path: &Path, options: &WalkOptions) -> Walk {
+        let mut walk = Walk::default();
+        for entry in walk_files(path, options.clone()) {
+            match entry {
+                WalkEntry::File(file) => walk.files.push(file),
+                WalkEntry::Skipped(skipped) => walk.skipped.push(skipped),
+                WalkEntry::Warning(warning) => walk.warnings.push(warning),
+            }
+        }
+        walk
+    }
+
+    //makes an empty directory for a test to build a file tree in
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("line-counter-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn walk_honors_nested_ignore_files() {
+        let dir = test_dir("walk-ignore");
+        fs::create_dir_all(dir.join("target/debug")).unwrap();
+        fs::create_dir_all(dir.join("src/generated")).unwrap();
+        fs::write(dir.join(".gitignore"), "target/\n*.log\n").unwrap();
+        fs::write(dir.join("src/.ignore"), "generated/\n!keep.log\n").unwrap();
+        fs::write(dir.join("target/debug/out"), "").unwrap();
+        fs::write(dir.join("src/generated/gen.rs"), "").unwrap();
+        fs::write(dir.join("src/lib.rs"), "").unwrap();
+        fs::write(dir.join("src/debug.log"), "").unwrap();
+        fs::write(dir.join("src/keep.log"), "").unwrap();
+
+        let options = WalkOptions { recursive: true, honor_ignore_files: true, ..WalkOptions::default() };
+        let mut files: Vec<PathBuf> = list_files(&dir, &options).files.into_iter().map(|p| p.strip_prefix(&dir).unwrap().to_path_buf()).collect();
+        files.sort();
+        assert_eq!(files, vec![PathBuf::from(".gitignore"), PathBuf::from("src/.ignore"), PathBuf::from("src/keep.log"), PathBuf::from("src/lib.rs")]);
+
+        let options = WalkOptions { recursive: true, honor_ignore_files: false, ..WalkOptions::default() };
+        assert_eq!(list_files(&dir, &options).files.len(), 7);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+    #[cfg(unix)]
+    #[test]
+    fn walk_skips_excluded_paths_before_looking_them_up() {
+        let dir = test_dir("walk-broken-excluded");
+        fs::write(dir.join("a.txt"), "").unwrap();
+        fs::write(dir.join(".gitignore"), "ignored-link\n").unwrap();
+        std::os::unix::fs::symlink(dir.join("missing"), dir.join("ignored-link")).unwrap();
+        std::os::unix::fs::symlink(dir.join("missing"), dir.join("excluded-link")).unwrap();
+
+        let options = WalkOptions { honor_ignore_files: true, excludes: vec![PathPattern::new("excluded-link").unwrap()], ..WalkOptions::default() };
+        let walk = list_files(&dir, &options);
+        assert_eq!(walk.files.len(), 2);
+        assert!(walk.warnings.is_empty(), "{:?}", walk.warnings);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+    #[test]
+    fn walk_excludes_and_includes() {
+        let dir = test_dir("walk-globs");
+        fs::create_dir_all(dir.join("vendor/lib")).unwrap();
+        fs::create_dir_all(dir.join("src/vendor")).unwrap();
+        fs::write(dir.join("vendor/lib/a.rs"), "").unwrap();
+        fs::write(dir.join("src/vendor/b.rs"), "").unwrap();
+        fs::write(dir.join("src/main.rs"), "").unwrap();
+        fs::write(dir.join("src/notes.md"), "").unwrap();
+
+        let options = WalkOptions {
+            recursive: true,
+            excludes: vec![PathPattern::new("/vendor/").unwrap()],
+            includes: vec![PathPattern::new("src/**/*.rs").unwrap()],
+            ..WalkOptions::default()
+        };
+        let mut files: Vec<PathBuf> = list_files(&dir, &options).files.into_iter().map(|p| p.strip_prefix(&dir).unwrap().to_path_buf()).collect();
+        files.sort();
+        assert_eq!(files, vec![PathBuf::from("src/main.rs"), PathBuf::from("src/vendor/b.rs")]);
+
+        //a directory that matches includes everything in it
+        for include in ["src/", "src", "/src/vendor"] {
+            let options = WalkOptions { recursive: true, includes: vec![PathPattern::new(include).unwrap()], ..WalkOptions::default() };
+            let mut files: Vec<PathBuf> = list_files(&dir, &options).files.into_iter().map(|p| p.strip_prefix(&dir).unwrap().to_path_buf()).collect();
+            files.sort();
+            let expected = if include == "/src/vendor" { vec!["src/vendor/b.rs"] } else { vec!["src/main.rs", "src/notes.md", "src/vendor/b.rs"] };
+            assert_eq!(files, expected.iter().map(PathBuf::from).collect::<Vec<_>>(), "--include {}", include);
+        }
+
+        //unanchored patterns match at any depth
+        let options = WalkOptions { recursive: true, excludes: vec![PathPattern::new("vendor").unwrap()], ..WalkOptions::default() };
+        let mut files: Vec<PathBuf> = list_files(&dir, &options).files.into_iter().map(|p| p.strip_prefix(&dir).unwrap().to_path_buf()).collect();
+        files.sort();
+        assert_eq!(files, vec![PathBuf::from("src/main.rs"), PathBuf::from("src/notes.md")]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+    #[test]
+    fn walk_reports_errors_and_keeps_going() {
+        let dir = test_dir("walk-errors");
+        fs::write(dir.join("a.txt"), "").unwrap();
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(dir.join("missing"), dir.join("broken")).unwrap();
+
+        let walk = list_files(&dir, &WalkOptions::default());
+        assert_eq!(walk.files, vec![dir.join("a.txt")]);
+        #[cfg(unix)]
+        {
+            assert_eq!(walk.warnings.len(), 1);
+            assert_eq!(walk.warnings[0].path, dir.join("broken"));
+            assert_eq!(walk.warnings[0].operation, Operation::GetMetadata);
+            assert_eq!(walk.warnings[0].kind, WarningKind::Io(io::ErrorKind::NotFound));
+        }
+
+        //a directory that doesn't exist is a warning, not a panic
+        let walk = list_files(&dir.join("missing"), &WalkOptions::default());
+        assert!(walk.files.is_empty());
+        assert_eq!(walk.warnings[0].operation, Operation::GetMetadata);
+        assert_eq!(walk.warnings[0].to_string(), format!("Could not get metadata of {}: entity not found", dir.join("missing").display()));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+    #[cfg(unix)]
+    #[test]
+    fn walk_symlink_policies() {
+        use std::os::unix::fs::symlink;
+        let dir = test_dir("walk-symlinks");
+        let outside = test_dir("walk-symlinks-outside");
+        fs::create_dir_all(dir.join("a")).unwrap();
+        fs::write(dir.join("a/file.txt"), "").unwrap();
+        fs::write(outside.join("other.txt"), "").unwrap();
+        symlink(&dir, dir.join("a/loop")).unwrap(); //a cycle back to the root
+        symlink(dir.join("a"), dir.join("b")).unwrap(); //a second way into a
+        symlink(&outside, dir.join("out")).unwrap(); //somewhere outside the root
+
+        let list = |symlinks| {
+            let walk = list_files(&dir, &WalkOptions { recursive: true, symlinks, ..WalkOptions::default() });
+            let mut files: Vec<PathBuf> = walk.files.iter().map(|p| p.strip_prefix(&dir).unwrap().to_path_buf()).collect();
+            files.sort();
+            (files, walk.warnings)
+        };
+
+        let (files, warnings) = list(SymlinkPolicy::Never);
+        assert_eq!(files, vec![PathBuf::from("a/file.txt")]);
+        assert!(warnings.is_empty());
+
+        let count = |warnings: &[TraversalWarning], kind| warnings.iter().filter(|w| w.kind == kind).count();
+        let (files, warnings) = list(SymlinkPolicy::WithinRoot);
+        assert_eq!(files.len(), 1); //a is only searched once, whether it's reached through a or b
+        assert_eq!(warnings.len(), 2);
+        assert_eq!((count(&warnings, WarningKind::SymlinkCycle), count(&warnings, WarningKind::AlreadySearched)), (1, 1));
+
+        let (files, warnings) = list(SymlinkPolicy::Follow);
+        assert_eq!(files.len(), 2);
+        assert!(files.contains(&PathBuf::from("out/other.txt")));
+        assert_eq!(warnings.len(), 2);
+        assert_eq!((count(&warnings, WarningKind::SymlinkCycle), count(&warnings, WarningKind::AlreadySearched)), (1, 1));
+
+        fs::remove_dir_all(&dir).unwrap();
+        fs::remove_dir_all(&outside).unwrap();
+    }
+    #[cfg(unix)]
+    #[test]
+    fn walk_symlink_diamond() {
+        use std::os::unix::fs::symlink;
+        //every level links twice to the next one, so there are 2^LEVELS ways to reach the bottom
+        const LEVELS: usize = 12;
+        let dir = test_dir("walk-diamond");
+        for level in 0..=LEVELS {
+            fs::create_dir_all(dir.join(level.to_string())).unwrap();
+        }
+        for level in 0..LEVELS {
+            symlink(dir.join((level + 1).to_string()), dir.join(format!("{}/left", level))).unwrap();
+            symlink(dir.join((level + 1).to_string()), dir.join(format!("{}/right", level))).unwrap();
+        }
+        fs::write(dir.join(format!("{}/file.txt", LEVELS)), "").unwrap();
+
+        for jobs in [1, 4] {
+            let walk = list_files(&dir, &WalkOptions { recursive: true, jobs, ..WalkOptions::default() });
+            assert_eq!(walk.files.len(), 1);
+            //every level is searched through the left links from the level above, which come first,
+            //so the way to it right under the root and the right link to it are warnings
+            assert_eq!(walk.warnings.len(), 2 * LEVELS);
+            assert!(walk.warnings.iter().all(|w| w.kind == WarningKind::AlreadySearched));
+        }
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+    #[cfg(unix)]
+    #[test]
+    fn walk_searches_the_first_way_to_a_directory() {
+        use std::os::unix::fs::symlink;
+        let dir = test_dir("walk-first-way");
+        let outside = test_dir("walk-first-way-outside");
+        fs::write(outside.join("file.txt"), "").unwrap();
+        //the deeper link takes longer to reach, but comes first by name
+        fs::create_dir_all(dir.join("a/b/c")).unwrap();
+        symlink(&outside, dir.join("a/b/c/d")).unwrap();
+        symlink(&outside, dir.join("z")).unwrap();
+
+        for jobs in [1, 8, 8, 8, 8] {
+            let walk = list_files(&dir, &WalkOptions { recursive: true, jobs, symlinks: SymlinkPolicy::Follow, ..WalkOptions::default() });
+            assert_eq!(walk.files, vec![dir.join("a/b/c/d/file.txt")]);
+            assert_eq!(walk.warnings.len(), 1);
+            assert_eq!((walk.warnings[0].path.clone(), walk.warnings[0].kind), (dir.join("z"), WarningKind::AlreadySearched));
+        }
+
+        fs::remove_dir_all(&dir).unwrap();
+        fs::remove_dir_all(&outside).unwrap();
+    }
+    #[test]
+    fn walk_order_is_sorted_by_name() {
+        let dir = test_dir("walk-sorted");
+        //made in the reverse of the order they should be listed in
+        for name in ["z.txt", "m/b.txt", "m/a.txt", "b.txt", "a/z.txt", "a/a.txt", "A.txt"] {
+            let path = dir.join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "").unwrap();
+        }
+
+        for jobs in [1, 4] {
+            let walk = list_files(&dir, &WalkOptions { recursive: true, jobs, ..WalkOptions::default() });
+            let files: Vec<PathBuf> = walk.files.iter().map(|p| p.strip_prefix(&dir).unwrap().to_path_buf()).collect();
+            let expected = ["A.txt", "a/a.txt", "a/z.txt", "b.txt", "m/a.txt", "m/b.txt", "z.txt"];
+            assert_eq!(files, expected.iter().map(PathBuf::from).collect::<Vec<_>>());
+        }
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+    #[test]
+    fn walk_order_is_the_same_on_any_number_of_threads() {
+        let dir = test_dir("walk-threads");
+        for a in 0..6 {
+            for b in 0..6 {
+                fs::create_dir_all(dir.join(format!("{}/{}", a, b))).unwrap();
+                fs::write(dir.join(format!("{}/{}/file.txt", a, b)), "").unwrap();
+            }
+            fs::write(dir.join(format!("{}/file.txt", a)), "").unwrap();
+        }
+
+        let single = list_files(&dir, &WalkOptions { recursive: true, jobs: 1, ..WalkOptions::default() });
+        assert_eq!(single.files.len(), 42);
+        //a directory's files always come right after it, before anything else
+        for (i, file) in single.files.iter().enumerate().skip(1) {
+            let previous = single.files[i - 1].parent().unwrap();
+            assert!(file.starts_with(previous) || !single.files[i..].iter().any(|f| f.starts_with(previous)), "{} is out of order", file.display());
+        }
+        for _ in 0..5 {
+            let parallel = list_files(&dir, &WalkOptions { recursive: true, jobs: 8, ..WalkOptions::default() });
+            assert_eq!(parallel.files, single.files);
+        }
+
+        //stopping early doesn't wait for the rest of the walk
+        assert_eq!(walk_files(&dir, WalkOptions { recursive: true, jobs: 4, ..WalkOptions::default() }).take(3).count(), 3);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+    #[cfg(unix)]
+    #[test]
+    fn walk_skips_special_files() {
+        let dir = test_dir("walk-special");
+        fs::write(dir.join("a.txt"), "").unwrap();
+        let _socket = std::os::unix::net::UnixListener::bind(dir.join("socket")).unwrap();
+
+        let walk = list_files(&dir, &WalkOptions::default());
+        assert_eq!(walk.files, vec![dir.join("a.txt")]);
+        assert_eq!(walk.skipped, vec![SkippedFile { path: dir.join("socket"), kind: SpecialKind::Socket }]);
+        assert!(walk.warnings.is_empty());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}